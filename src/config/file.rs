@@ -0,0 +1,313 @@
+use super::{
+    global::Config,
+    migrate::{self, CURRENT_VERSION},
+};
+use crate::utils::{
+    self,
+    error::EnzoError,
+    query::{AnswerKind, Question},
+};
+use fs2::FileExt;
+use serde_yaml::{Mapping, Value};
+use std::{
+    env,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::prelude::*,
+    path::{Path, PathBuf},
+};
+
+/// How many previous versions of the config file are kept around.
+const BACKUPS: usize = 3;
+
+/// The global config file. Other enzo processes may use it at the same time,
+/// so it is only locked while it is being read or replaced. If another one
+/// saved the config since it was read, `write` applies this one's changes on
+/// top of that instead of overwriting them.
+pub struct ConfigFile {
+    path: PathBuf,
+    /// The file as it was read, if it existed.
+    loaded: Option<String>,
+    /// The config as it was read, serialized the way `write` would save it.
+    base: Option<String>,
+    /// The version of the config as it was read.
+    version: u64,
+}
+
+impl ConfigFile {
+    /// Works out where the config lives. In order of precedence that is
+    /// `explicit` (the `--config` flag), `$ENZO_CONFIG`,
+    /// `$XDG_CONFIG_HOME/enzo/config.yaml` and finally the legacy
+    /// `~/.enzo.config.yaml`, which enzo offers to move to the XDG location once.
+    /// It only asks when `interactive` and attached to a terminal.
+    pub fn locate(explicit: Option<&str>, interactive: bool) -> Result<PathBuf, EnzoError> {
+        if let Some(path) = explicit {
+            return Ok(PathBuf::from(path));
+        }
+        if let Some(path) = env::var_os("ENZO_CONFIG").filter(|p| !p.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
+
+        let xdg = xdg_path()?;
+        let mut legacy = utils::get_home_dir()?;
+        legacy.push(".enzo.config.yaml");
+        if xdg.exists() || !legacy.exists() {
+            return Ok(xdg);
+        }
+
+        let mut declined = utils::get_data_dir()?;
+        declined.push("keep-legacy-config");
+        let tty = atty::is(atty::Stream::Stdin) && atty::is(atty::Stream::Stdout);
+        if declined.exists() || !interactive || !tty {
+            return Ok(legacy);
+        }
+        let question = format!("Move {:?} to {:?}?", legacy, xdg);
+        let question = Question::new(
+            &question,
+            Some("y"),
+            None,
+            Some(vec!["enzo won't ask again if you answer no"]),
+        );
+        let mut answer = AnswerKind::Single(String::new());
+        question.ask(&mut answer);
+        match answer {
+            AnswerKind::Single(s) if s.is_empty() || s.to_lowercase().starts_with('y') => {
+                if let Some(parent) = xdg.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(&legacy, &xdg)?;
+                utils::success(format!("moved the config to {:?}", xdg));
+                Ok(xdg)
+            }
+            _ => {
+                if let Some(parent) = declined.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                File::create(&declined)?;
+                Ok(legacy)
+            }
+        }
+    }
+
+    pub fn open(path: PathBuf) -> Result<Self, EnzoError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(ConfigFile {
+            path,
+            loaded: None,
+            base: None,
+            version: CURRENT_VERSION,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the config, or an empty one if the file doesn't exist yet. A
+    /// config written by an older enzo is upgraded in memory and saved by the
+    /// next `write`.
+    pub fn read(&mut self) -> Result<Config, EnzoError> {
+        if !self.path.exists() {
+            return Ok(Config::default());
+        }
+        let buffer = {
+            let _lock = self.lock(false)?;
+            fs::read_to_string(&self.path)?
+        };
+        self.version = migrate::version_of(&serde_yaml::from_str(&buffer)?);
+        let config = Config::parse(&buffer)?;
+        self.base = Some(config.to_string()?);
+        self.loaded = Some(buffer);
+        Ok(config)
+    }
+
+    /// Saves `config` if it differs from what was read, replacing the file
+    /// atomically. The previous version is kept as `<path>.bak.1`, the one
+    /// before that as `<path>.bak.2` and so on. When the config is upgraded
+    /// the original is also kept as `<path>.v<version>.bak`.
+    pub fn write(&mut self, config: &Config) -> Result<(), EnzoError> {
+        let mut contents = config.to_string()?;
+        if self.version == CURRENT_VERSION && self.base.as_ref() == Some(&contents) {
+            return Ok(());
+        }
+
+        let _lock = self.lock(true)?;
+        let current = fs::read_to_string(&self.path).ok();
+        if let Some(current) = &current {
+            if Some(current) != self.loaded.as_ref() {
+                contents = self.rebase(&contents, current)?;
+                utils::info(
+                    "another enzo process changed the config meanwhile, kept its changes too",
+                );
+            }
+            let version = migrate::version_of(&serde_yaml::from_str(current)?);
+            if version < CURRENT_VERSION {
+                let backup = sibling(&self.path, &format!(".v{}.bak", version));
+                fs::copy(&self.path, &backup)?;
+                utils::info(format!(
+                    "upgraded the config from version {} to {}. The original was saved to {:?}",
+                    version, CURRENT_VERSION, backup
+                ));
+            }
+        }
+
+        let tmp = sibling(&self.path, ".tmp");
+        {
+            let mut file = File::create(&tmp)?;
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()?;
+        }
+        if self.path.exists() {
+            self.rotate_backups()?;
+        }
+        fs::rename(&tmp, &self.path)?;
+        self.loaded = Some(contents.clone());
+        self.base = Some(contents);
+        self.version = CURRENT_VERSION;
+        Ok(())
+    }
+
+    /// Applies the changes made to the config since it was read to `current`,
+    /// the config another process saved meanwhile.
+    fn rebase(&self, ours: &str, current: &str) -> Result<String, EnzoError> {
+        let base = match &self.base {
+            Some(base) => serde_yaml::from_str(base)?,
+            None => serde_yaml::to_value(Config::default())?,
+        };
+        let ours = serde_yaml::from_str(ours)?;
+        let theirs = serde_yaml::to_value(Config::parse(current)?)?;
+        let merged = merge(Some(&base), Some(&ours), Some(&theirs)).unwrap_or(Value::Null);
+        let config: Config = serde_yaml::from_value(merged)?;
+        config.to_string()
+    }
+
+    /// Takes the advisory lock next to the config, shared for reading and
+    /// exclusive for writing, waiting for any other enzo process that holds it.
+    fn lock(&self, exclusive: bool) -> Result<File, EnzoError> {
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(sibling(&self.path, ".lock"))?;
+        let locked = if exclusive {
+            FileExt::try_lock_exclusive(&lock)
+        } else {
+            FileExt::try_lock_shared(&lock)
+        };
+        if locked.is_err() {
+            utils::info("waiting for another enzo process to finish");
+            if exclusive {
+                FileExt::lock_exclusive(&lock)?;
+            } else {
+                FileExt::lock_shared(&lock)?;
+            }
+        }
+        Ok(lock)
+    }
+
+    fn rotate_backups(&self) -> Result<(), EnzoError> {
+        for i in (1..BACKUPS).rev() {
+            let from = sibling(&self.path, &format!(".bak.{}", i));
+            if from.exists() {
+                fs::rename(&from, sibling(&self.path, &format!(".bak.{}", i + 1)))?;
+            }
+        }
+        fs::copy(&self.path, sibling(&self.path, ".bak.1"))?;
+        Ok(())
+    }
+}
+
+fn xdg_path() -> Result<PathBuf, EnzoError> {
+    let mut path = match env::var_os("XDG_CONFIG_HOME").filter(|p| !p.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let mut home = utils::get_home_dir()?;
+            home.push(".config");
+            home
+        }
+    };
+    path.push("enzo");
+    path.push("config.yaml");
+    Ok(path)
+}
+
+/// Three-way merges two versions of a document. Mappings are merged key by
+/// key and anything else is taken as a whole. Where both changed the same
+/// thing, `ours` wins.
+fn merge(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+    if ours == base {
+        return theirs.cloned();
+    }
+    if theirs == base || theirs == ours {
+        return ours.cloned();
+    }
+    match (ours, theirs) {
+        (Some(Value::Mapping(o)), Some(Value::Mapping(t))) => {
+            let b = match base {
+                Some(Value::Mapping(b)) => Some(b),
+                _ => None,
+            };
+            let mut keys = t.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+            keys.extend(
+                o.iter()
+                    .map(|(k, _)| k.clone())
+                    .filter(|k| t.get(k).is_none()),
+            );
+            let mut merged = Mapping::new();
+            for key in keys {
+                let value = merge(b.and_then(|b| b.get(&key)), o.get(&key), t.get(&key));
+                if let Some(value) = value {
+                    merged.insert(key, value);
+                }
+            }
+            Some(Value::Mapping(merged))
+        }
+        _ => ours.cloned(),
+    }
+}
+
+/// `path` with `suffix` appended to its file name.
+pub(crate) fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::workspace::{project::Project, WorkspaceName};
+
+    #[test]
+    fn keep_changes_of_concurrent_processes() {
+        let dir = env::temp_dir().join(format!("enzo-config-{}", std::process::id()));
+        let path = dir.join("config.yaml");
+        let mut first = ConfigFile::open(path.clone()).unwrap();
+        let mut config = first.read().unwrap();
+        config.add_workspace("old", PathBuf::from("dev/old"));
+        first.write(&config).unwrap();
+
+        // both read the config, then save their own change
+        let mut second = ConfigFile::open(path.clone()).unwrap();
+        let mut ours = first.read().unwrap();
+        let mut theirs = second.read().unwrap();
+        ours.add_project(
+            PathBuf::from("dev/old/horizons"),
+            Project::new("horizons".into(), "old".into(), String::new(), vec![]),
+        );
+        theirs.add_workspace("new", PathBuf::from("dev/new"));
+        theirs.remove_workspace(&WorkspaceName::from("old"));
+        second.write(&theirs).unwrap();
+        first.write(&ours).unwrap();
+
+        let config = ConfigFile::open(path).unwrap().read().unwrap();
+        assert_eq!(config.get_path(&WorkspaceName::from("old")), None);
+        assert!(config.get_path(&WorkspaceName::from("new")).is_some());
+        assert!(config
+            .get_project(&PathBuf::from("dev/old/horizons"))
+            .is_some());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,14 +1,42 @@
+use super::migrate::{self, CURRENT_VERSION};
 use crate::{
-    utils::error::{EnzoError, EnzoErrorKind},
+    utils::{
+        self,
+        error::{EnzoError, EnzoErrorKind},
+    },
     workspace::{project::Project, WorkspaceName},
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, convert::TryFrom, fs::File, io::prelude::*, path::PathBuf};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs::File,
+    io::prelude::*,
+    path::{Path, PathBuf},
+};
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    version: u64,
     workspaces: HashMap<WorkspaceName, PathBuf>,
     projects: HashMap<PathBuf, Project>,
+    #[serde(default)]
+    hosts: HashMap<String, String>,
+    #[serde(default)]
+    ssh_keys: Vec<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            version: CURRENT_VERSION,
+            workspaces: HashMap::new(),
+            projects: HashMap::new(),
+            hosts: HashMap::new(),
+            ssh_keys: vec![],
+        }
+    }
 }
 
 impl TryFrom<PathBuf> for Config {
@@ -19,8 +47,7 @@ impl TryFrom<PathBuf> for Config {
             let mut file = File::open(path)?;
             let mut buffer = String::new();
             file.read_to_string(&mut buffer)?;
-            let config = serde_yaml::from_str(&buffer)?;
-            Ok(config)
+            Config::parse(&buffer)
         } else {
             let msg = format!("Could not find `enzo.config.yaml` at {:?}", path);
             Err(EnzoError::new(msg, EnzoErrorKind::ConfigError))
@@ -29,12 +56,22 @@ impl TryFrom<PathBuf> for Config {
 }
 
 impl Config {
+    /// Parses a config document, upgrading it first if it was written by an
+    /// older version of enzo.
+    pub fn parse(buffer: &str) -> Result<Self, EnzoError> {
+        let mut doc = serde_yaml::from_str(buffer)?;
+        migrate::migrate(&mut doc)?;
+        let config = serde_yaml::from_value(doc)?;
+        Ok(config)
+    }
+
     pub fn add_workspace<T: Into<WorkspaceName>>(
         &mut self,
         name: T,
         path: PathBuf,
     ) -> Option<PathBuf> {
-        self.workspaces.insert(name.into(), path)
+        self.workspaces
+            .insert(name.into(), utils::contract_path(&path))
     }
 
     pub fn remove_workspace<'a, T>(&mut self, name: &'a T) -> Option<PathBuf>
@@ -44,11 +81,42 @@ impl Config {
         self.workspaces.remove(name.into())
     }
 
-    pub fn get_path<'a, T>(&self, name: &'a T) -> Option<&PathBuf>
+    /// Renames a workspace and every project that belongs to it. Returns the
+    /// workspace's path, or `None` if there is no workspace called `from`.
+    pub fn rename_workspace(
+        &mut self,
+        from: &WorkspaceName,
+        to: WorkspaceName,
+    ) -> Option<&PathBuf> {
+        if !self.workspaces.contains_key(from) {
+            return None;
+        }
+        let renamed = self
+            .workspaces
+            .keys()
+            .filter_map(|name| Some((name.clone(), name.renamed(from, &to)?)))
+            .collect::<Vec<_>>();
+        for (old, new) in renamed {
+            if let Some(path) = self.workspaces.remove(&old) {
+                self.workspaces.insert(new, path);
+            }
+        }
+        for project in self.projects.values_mut() {
+            if let Some(new) = project.workspace().renamed(from, &to) {
+                project.set_workspace(new);
+            }
+        }
+        self.workspaces.get(&to)
+    }
+
+    /// The path of a workspace with `~` and environment variables expanded.
+    pub fn get_path<'a, T>(&self, name: &'a T) -> Option<PathBuf>
     where
         &'a T: Into<&'a WorkspaceName>,
     {
-        self.workspaces.get(name.into())
+        self.workspaces
+            .get(name.into())
+            .map(|p| utils::expand_path(p))
     }
 
     pub fn get_path_mut<'a, T>(&mut self, name: &'a T) -> Option<&mut PathBuf>
@@ -66,6 +134,31 @@ impl Config {
         self.projects.remove(path)
     }
 
+    /// Re-keys the project at `from`, and any project nested inside it, to
+    /// live under `to` in the workspace `workspace`. Returns how many projects
+    /// were moved.
+    pub fn move_project(&mut self, from: &Path, to: &Path, workspace: &WorkspaceName) -> usize {
+        let moved = self
+            .projects
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in &moved {
+            if let Some(mut project) = self.projects.remove(path) {
+                let new_path = to.join(path.strip_prefix(from).unwrap());
+                if path == from {
+                    if let Some(name) = to.file_name() {
+                        project.set_name(name.to_string_lossy().into_owned());
+                    }
+                }
+                project.set_workspace(workspace.clone());
+                self.projects.insert(new_path, project);
+            }
+        }
+        moved.len()
+    }
+
     pub fn get_project(&self, path: &PathBuf) -> Option<&Project> {
         self.projects.get(path)
     }
@@ -74,16 +167,38 @@ impl Config {
         self.projects.get_mut(path)
     }
 
+    /// Every workspace and its path, with `~` and environment variables expanded.
+    pub fn workspaces(&self) -> impl Iterator<Item = (&WorkspaceName, PathBuf)> {
+        self.workspaces
+            .iter()
+            .map(|(name, path)| (name, utils::expand_path(path)))
+    }
+
+    pub fn projects(&self) -> impl Iterator<Item = (&PathBuf, &Project)> {
+        self.projects.iter()
+    }
+
+    pub fn add_host<T: Into<String>>(&mut self, alias: T, base: T) -> Option<String> {
+        self.hosts.insert(alias.into(), base.into())
+    }
+
+    pub fn get_host(&self, alias: &str) -> Option<&String> {
+        self.hosts.get(alias)
+    }
+
+    pub fn ssh_keys(&self) -> &[PathBuf] {
+        &self.ssh_keys
+    }
+
     pub fn resolve_path<'a, T: Into<&'a str>>(&self, path: T) -> Option<(WorkspaceName, PathBuf)> {
         let path: &str = path.into();
         let mut i = path.len();
         if path.ends_with("/") {
-            i = i - 1;
+            i -= 1;
         }
         while i > 0 {
-            if let Some(path_buf) = self.get_path(&path[..i].into()) {
-                let mut resolved_path = path_buf.clone();
-                if path[i..].len() > 0 {
+            if let Some(mut resolved_path) = self.get_path(&path[..i].into()) {
+                if !path[i..].is_empty() {
                     resolved_path.push(&path[i + 1..]);
                 }
                 return Some((path[..i].into(), resolved_path));
@@ -112,6 +227,7 @@ mod test {
         let config = Config {
             workspaces,
             projects,
+            ..Default::default()
         };
 
         assert_eq!(config.resolve_path("hackgt"), None);
@@ -119,6 +235,112 @@ mod test {
         assert_eq!(config.resolve_path("/"), None);
     }
 
+    #[test]
+    fn rename_workspace() {
+        let mut config = Config::default();
+        config.add_workspace("hackgt", PathBuf::from("dev/hackgt"));
+        config.add_project(
+            PathBuf::from("dev/hackgt/horizons"),
+            Project::new("horizons".into(), "hackgt".into(), String::new(), vec![]),
+        );
+
+        assert_eq!(config.rename_workspace(&"hgt".into(), "nope".into()), None);
+        assert_eq!(
+            config.rename_workspace(&"hackgt".into(), "hgt".into()),
+            Some(&PathBuf::from("dev/hackgt"))
+        );
+        assert_eq!(config.get_path(&"hackgt".into()), None);
+        assert_eq!(
+            config.get_path(&"hgt".into()),
+            Some(PathBuf::from("dev/hackgt"))
+        );
+        let project = config
+            .get_project(&PathBuf::from("dev/hackgt/horizons"))
+            .unwrap();
+        assert_eq!(project.workspace(), &WorkspaceName::from("hgt"));
+    }
+
+    #[test]
+    fn rename_nested_workspaces() {
+        let mut config = Config::default();
+        config.add_workspace("hackgt", PathBuf::from("dev/hackgt"));
+        config.add_workspace("hackgt.websites", PathBuf::from("dev/hackgt/websites"));
+        config.add_workspace("hackgt/infra", PathBuf::from("dev/infra"));
+        config.add_workspace("hackgtx", PathBuf::from("dev/hackgtx"));
+        config.add_project(
+            PathBuf::from("dev/hackgt/websites/horizons"),
+            Project::new(
+                "horizons".into(),
+                "hackgt.websites".into(),
+                String::new(),
+                vec![],
+            ),
+        );
+
+        config.rename_workspace(&"hackgt".into(), "hgt".into());
+        assert_eq!(
+            config.get_path(&"hgt.websites".into()),
+            Some(PathBuf::from("dev/hackgt/websites"))
+        );
+        assert_eq!(
+            config.get_path(&"hgt/infra".into()),
+            Some(PathBuf::from("dev/infra"))
+        );
+        assert_eq!(config.get_path(&"hackgt.websites".into()), None);
+        assert_eq!(
+            config.get_path(&"hackgtx".into()),
+            Some(PathBuf::from("dev/hackgtx"))
+        );
+        let project = config
+            .get_project(&PathBuf::from("dev/hackgt/websites/horizons"))
+            .unwrap();
+        assert_eq!(project.workspace(), &WorkspaceName::from("hgt.websites"));
+    }
+
+    #[test]
+    fn move_project() {
+        let mut config = Config::default();
+        config.add_project(
+            PathBuf::from("dev/hackgt/horizons"),
+            Project::new("horizons".into(), "hackgt".into(), String::new(), vec![]),
+        );
+        config.add_project(
+            PathBuf::from("dev/hackgt/horizons/docs"),
+            Project::new("docs".into(), "hackgt".into(), String::new(), vec![]),
+        );
+        config.add_project(
+            PathBuf::from("dev/hackgt/horizons-old"),
+            Project::new(
+                "horizons-old".into(),
+                "hackgt".into(),
+                String::new(),
+                vec![],
+            ),
+        );
+
+        let moved = config.move_project(
+            Path::new("dev/hackgt/horizons"),
+            Path::new("life/college/h2"),
+            &"college".into(),
+        );
+        assert_eq!(moved, 2);
+        assert!(config
+            .get_project(&PathBuf::from("dev/hackgt/horizons"))
+            .is_none());
+        assert!(config
+            .get_project(&PathBuf::from("dev/hackgt/horizons-old"))
+            .is_some());
+        let project = config
+            .get_project(&PathBuf::from("life/college/h2"))
+            .unwrap();
+        assert_eq!(project.name(), "h2");
+        assert_eq!(project.workspace(), &WorkspaceName::from("college"));
+        let docs = config
+            .get_project(&PathBuf::from("life/college/h2/docs"))
+            .unwrap();
+        assert_eq!(docs.name(), "docs");
+    }
+
     #[test]
     fn resolve_path_some() {
         let input = vec![
@@ -138,6 +360,7 @@ mod test {
         let config = Config {
             workspaces,
             projects,
+            ..Default::default()
         };
 
         assert_eq!(
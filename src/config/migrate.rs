@@ -0,0 +1,150 @@
+use crate::utils::{
+    self,
+    error::{EnzoError, EnzoErrorKind},
+};
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+
+type Migration = fn(&mut Mapping) -> Result<(), EnzoError>;
+
+/// `MIGRATIONS[i]` upgrades a version `i` config document to version `i + 1`.
+/// Add a step here whenever the layout of `Config` changes.
+const MIGRATIONS: [Migration; 3] = [v0_to_v1, v1_to_v2, v2_to_v3];
+
+/// The config version this build of enzo reads and writes.
+pub const CURRENT_VERSION: u64 = MIGRATIONS.len() as u64;
+
+/// The version of a config document. Documents from before versioning have
+/// no `version` key and count as version 0.
+pub fn version_of(doc: &Value) -> u64 {
+    match doc {
+        Value::Mapping(mapping) => mapping
+            .get(&Value::from("version"))
+            .and_then(Value::as_u64)
+            .unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Upgrades `doc` to the current version one step at a time and returns the
+/// version it started at.
+pub fn migrate(doc: &mut Value) -> Result<u64, EnzoError> {
+    let from = version_of(doc);
+    if from > CURRENT_VERSION {
+        let msg = format!(
+            "The config file is version {} but this enzo only understands up to version {}.\nhint: Upgrade enzo to use this config",
+            from, CURRENT_VERSION
+        );
+        return Err(EnzoError::new(msg, EnzoErrorKind::ConfigError));
+    }
+    if let Value::Null = doc {
+        *doc = Value::Mapping(Mapping::new());
+    }
+    let mapping = match doc {
+        Value::Mapping(mapping) => mapping,
+        _ => {
+            return Err(EnzoError::new(
+                "Expected the config file to be a mapping",
+                EnzoErrorKind::ParseError,
+            ))
+        }
+    };
+    for version in from..CURRENT_VERSION {
+        MIGRATIONS[version as usize](mapping)?;
+        mapping.insert(Value::from("version"), Value::from(version + 1));
+    }
+    Ok(from)
+}
+
+/// Version 0 configs predate the `version` key. Their layout is unchanged, so
+/// they only get stamped with a version.
+fn v0_to_v1(_: &mut Mapping) -> Result<(), EnzoError> {
+    Ok(())
+}
+
+/// Version 2 stores workspace paths under the home directory as `~/...` so
+/// that the config can be shared between machines.
+fn v1_to_v2(doc: &mut Mapping) -> Result<(), EnzoError> {
+    if let Some(Value::Mapping(workspaces)) = doc.get_mut(&Value::from("workspaces")) {
+        for (_, path) in workspaces.iter_mut() {
+            if let Value::String(s) = path {
+                *s = utils::contract_path(Path::new(s.as_str()))
+                    .to_string_lossy()
+                    .into_owned();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Version 3 can keep the todos of a project in its `enzo.yaml`. Projects
+/// from before keep theirs in the config, where they have always been.
+fn v2_to_v3(doc: &mut Mapping) -> Result<(), EnzoError> {
+    if let Some(Value::Mapping(projects)) = doc.get_mut(&Value::from("projects")) {
+        for (_, project) in projects.iter_mut() {
+            if let Value::Mapping(project) = project {
+                let key = Value::from("todo_store");
+                if !project.contains_key(&key) {
+                    project.insert(key, Value::from("private"));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn migrate_unversioned() {
+        let mut doc: Value = serde_yaml::from_str("workspaces: {}\nprojects: {}\n").unwrap();
+        assert_eq!(version_of(&doc), 0);
+        assert_eq!(migrate(&mut doc).unwrap(), 0);
+        assert_eq!(version_of(&doc), CURRENT_VERSION);
+    }
+
+    #[test]
+    fn migrate_home_relative_workspaces() {
+        let home = dirs::home_dir().unwrap();
+        let input = format!(
+            "version: 1\nworkspaces:\n  hackgt: {}/dev/hackgt\n  tmp: /tmp\n",
+            home.to_string_lossy()
+        );
+        let mut doc: Value = serde_yaml::from_str(&input).unwrap();
+        migrate(&mut doc).unwrap();
+        let workspaces = &doc["workspaces"];
+        assert_eq!(workspaces["hackgt"], Value::from("~/dev/hackgt"));
+        assert_eq!(workspaces["tmp"], Value::from("/tmp"));
+    }
+
+    #[test]
+    fn migrate_projects_to_private_todos() {
+        let input = "version: 2\nprojects:\n  /dev/horizons:\n    name: horizons\n    todos: []\n  /dev/docs:\n    name: docs\n    todo_store: repo\n";
+        let mut doc: Value = serde_yaml::from_str(input).unwrap();
+        migrate(&mut doc).unwrap();
+        let projects = &doc["projects"];
+        assert_eq!(
+            projects["/dev/horizons"]["todo_store"],
+            Value::from("private")
+        );
+        assert_eq!(projects["/dev/docs"]["todo_store"], Value::from("repo"));
+    }
+
+    #[test]
+    fn migrate_current_is_noop() {
+        let input = format!("version: {}\nworkspaces: {{}}\n", CURRENT_VERSION);
+        let mut doc: Value = serde_yaml::from_str(&input).unwrap();
+        let before = doc.clone();
+        assert_eq!(migrate(&mut doc).unwrap(), CURRENT_VERSION);
+        assert_eq!(doc, before);
+    }
+
+    #[test]
+    fn migrate_newer_fails() {
+        let input = format!("version: {}\n", CURRENT_VERSION + 1);
+        let mut doc: Value = serde_yaml::from_str(&input).unwrap();
+        assert!(migrate(&mut doc).is_err());
+    }
+}
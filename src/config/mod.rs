@@ -1,3 +1,5 @@
+pub mod file;
 pub mod global;
+pub mod migrate;
 pub mod project;
 pub mod section;
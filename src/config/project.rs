@@ -1,11 +1,19 @@
-use super::section::{self, ExecutionContext, Instruction, Section};
+use super::{
+    file,
+    section::{self, ExecutionContext, Instruction, Section},
+};
 use crate::{
     todos::todo::Todo,
     utils::error::{EnzoError, EnzoErrorKind},
 };
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::prelude::*, path::PathBuf};
+use serde_yaml::{Mapping, Value};
+use std::{
+    fs::{self, File},
+    io::prelude::*,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProjectConfig {
@@ -17,7 +25,7 @@ impl ProjectConfig {
     pub fn configure(&self, ctx: &ExecutionContext) -> Result<(), EnzoError> {
         if let Some(ref mapping) = self.configure {
             for (section, instructions) in mapping.iter() {
-                println!(
+                eprintln!(
                     "{} {}",
                     ansi_term::Color::White.bold().paint("executing section"),
                     ansi_term::Color::Purple.bold().paint(section.0.clone())
@@ -44,3 +52,70 @@ pub fn read_from(path: &PathBuf) -> Result<ProjectConfig, EnzoError> {
     let project_config = serde_yaml::from_str(&buffer)?;
     Ok(project_config)
 }
+
+/// Replaces the todos in the `enzo.yaml` at `path`, creating it if needed.
+/// Every other key is kept in its place, although comments are lost.
+pub fn write_todos(path: &Path, todos: &[Todo]) -> Result<(), EnzoError> {
+    let mut doc = if path.exists() {
+        serde_yaml::from_str(&fs::read_to_string(path)?)?
+    } else {
+        Value::Null
+    };
+    if doc.is_null() {
+        doc = Value::Mapping(Mapping::new());
+    }
+    let mapping = match doc.as_mapping_mut() {
+        Some(mapping) => mapping,
+        None => {
+            let msg = format!("Expected {:?} to contain a mapping", path);
+            return Err(EnzoError::new(msg, EnzoErrorKind::ConfigError));
+        }
+    };
+
+    let key = Value::from("todos");
+    let todos = serde_yaml::to_value(todos)?;
+    match mapping.get_mut(&key) {
+        Some(value) => *value = todos,
+        None => {
+            mapping.insert(key, todos);
+        }
+    }
+
+    // replaced atomically like the global config, so a crash can't leave half a file
+    let tmp = file::sibling(path, ".tmp");
+    {
+        let mut file = File::create(&tmp)?;
+        file.write_all((serde_yaml::to_string(&doc)? + "\n").as_bytes())?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn write_todos_keeps_other_keys() {
+        let path = std::env::temp_dir().join(format!("enzo-{}.yaml", std::process::id()));
+        fs::write(
+            &path,
+            "name: horizons\ntodos:\n  - name: old\nconfigure:\n  build:\n    - cargo build\n",
+        )
+        .unwrap();
+
+        write_todos(&path, &[Todo::new("new".into(), None)]).unwrap();
+        let buffer = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let keys = ["name:", "todos:", "configure:"];
+        let positions = keys
+            .iter()
+            .map(|key| buffer.find(key).unwrap())
+            .collect::<Vec<_>>();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert!(buffer.contains("name: new"));
+        assert!(!buffer.contains("old"));
+    }
+}
@@ -61,7 +61,7 @@ fn execute_instruction(
                 });
             };
             let question = Question {
-                question: &question,
+                question,
                 default: default.as_ref().map(|i| i.as_str()),
                 hints: Some(hints_str),
                 prefill: None,
@@ -72,7 +72,7 @@ fn execute_instruction(
         }
         Instruction::Run(commands) => {
             for command in commands {
-                println!(
+                eprintln!(
                     "$ {}",
                     ansi_term::Color::White.dimmed().paint(command.clone())
                 );
@@ -99,7 +99,7 @@ fn execute_instruction(
 }
 
 fn run_command(
-    command: &String,
+    command: &str,
     answers: &HashMap<String, AnswerKind>,
     ctx: &ExecutionContext,
 ) -> Result<(), EnzoError> {
@@ -108,7 +108,7 @@ fn run_command(
     let mut args = vec![];
 
     for arg in it {
-        args.push(process_arg(&arg, answers, ctx));
+        args.push(process_arg(arg, answers, ctx));
     }
 
     Command::new(cmd).args(&args).status()?;
@@ -0,0 +1,200 @@
+use crate::{config::global::Config, git, workspace::WorkspaceName};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Severity::Error => ansi_term::Color::Red.bold().paint("errors"),
+            Severity::Warning => ansi_term::Color::Yellow.bold().paint("warnings"),
+            Severity::Info => ansi_term::Color::Blue.bold().paint("info"),
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+/// A change to the config that resolves a `Problem`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fix {
+    RemoveWorkspace(WorkspaceName),
+    RemoveProject(PathBuf),
+    Relink(PathBuf, WorkspaceName),
+    SetSrc(PathBuf, String),
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fix::RemoveWorkspace(name) => write!(f, "remove workspace {}", name.0),
+            Fix::RemoveProject(path) => write!(f, "remove project {:?}", path),
+            Fix::Relink(path, name) => write!(f, "move {:?} to workspace {}", path, name.0),
+            Fix::SetSrc(path, src) => write!(f, "set the src of {:?} to {}", path, src),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Problem {
+    pub severity: Severity,
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+impl Problem {
+    fn new(severity: Severity, message: String, fix: Option<Fix>) -> Self {
+        Problem {
+            severity,
+            message,
+            fix,
+        }
+    }
+}
+
+/// Checks every workspace and project in `config` against the filesystem and
+/// git. Problems are sorted from most to least severe.
+pub fn check(config: &Config) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    for (name, path) in config.workspaces() {
+        if !path.is_dir() {
+            problems.push(Problem::new(
+                Severity::Error,
+                format!(
+                    "workspace {} points to {:?}, which does not exist",
+                    name.0, path
+                ),
+                Some(Fix::RemoveWorkspace(name.clone())),
+            ));
+        }
+    }
+
+    for (path, project) in config.projects() {
+        if !path.is_dir() {
+            problems.push(Problem::new(
+                Severity::Error,
+                format!("project {} at {:?} does not exist", project.name(), path),
+                Some(Fix::RemoveProject(path.clone())),
+            ));
+            continue;
+        }
+
+        let owner = owning_workspace(config, path);
+        match config.get_path(project.workspace()) {
+            None => problems.push(Problem::new(
+                Severity::Warning,
+                format!(
+                    "project {} belongs to workspace {}, which does not exist",
+                    project.name(),
+                    project.workspace().0
+                ),
+                // a project that no workspace contains can only be forgotten
+                Some(match owner {
+                    Some(owner) => Fix::Relink(path.clone(), owner),
+                    None => Fix::RemoveProject(path.clone()),
+                }),
+            )),
+            Some(workspace_path) if !path.starts_with(&workspace_path) => {
+                problems.push(Problem::new(
+                    Severity::Warning,
+                    format!(
+                        "project {} at {:?} is outside of its workspace {}",
+                        project.name(),
+                        path,
+                        project.workspace().0
+                    ),
+                    owner.map(|owner| Fix::Relink(path.clone(), owner)),
+                ))
+            }
+            _ => {}
+        }
+
+        match git::origin_url(path) {
+            Err(_) => problems.push(Problem::new(
+                Severity::Warning,
+                format!("project {} at {:?} is not a git repo", project.name(), path),
+                None,
+            )),
+            Ok(Some(origin)) if origin != project.src => problems.push(Problem::new(
+                Severity::Info,
+                format!(
+                    "project {} has src {} but its origin is {}",
+                    project.name(),
+                    project.src,
+                    origin
+                ),
+                Some(Fix::SetSrc(path.clone(), origin)),
+            )),
+            _ => {}
+        }
+    }
+
+    problems.sort_by_key(|problem| problem.severity);
+    problems
+}
+
+pub fn apply(config: &mut Config, fix: &Fix) {
+    match fix {
+        Fix::RemoveWorkspace(name) => {
+            config.remove_workspace(name);
+        }
+        Fix::RemoveProject(path) => {
+            config.remove_project(path);
+        }
+        Fix::Relink(path, name) => {
+            if let Some(project) = config.get_project_mut(path) {
+                project.set_workspace(name.clone());
+            }
+        }
+        Fix::SetSrc(path, src) => {
+            if let Some(project) = config.get_project_mut(path) {
+                project.src = src.clone();
+            }
+        }
+    }
+}
+
+/// The most specific existing workspace whose directory contains `path`.
+fn owning_workspace(config: &Config, path: &Path) -> Option<WorkspaceName> {
+    config
+        .workspaces()
+        .filter(|(_, workspace_path)| workspace_path.is_dir() && path.starts_with(workspace_path))
+        .max_by_key(|(_, workspace_path)| workspace_path.components().count())
+        .map(|(name, _)| name.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::workspace::project::Project;
+
+    #[test]
+    fn check_missing_entries() {
+        let mut config = Config::default();
+        config.add_workspace("gone", PathBuf::from("/enzo/doctor/gone"));
+        config.add_project(
+            PathBuf::from("/enzo/doctor/gone/horizons"),
+            Project::new("horizons".into(), "gone".into(), String::new(), vec![]),
+        );
+
+        let problems = check(&config);
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().all(|p| p.severity == Severity::Error));
+        assert!(problems
+            .iter()
+            .any(|p| p.fix == Some(Fix::RemoveWorkspace("gone".into()))));
+
+        for problem in &problems {
+            apply(&mut config, problem.fix.as_ref().unwrap());
+        }
+        assert!(check(&config).is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use super::{auth::Auth, fetch_options, progress::Reporter};
+use crate::utils::{self, error::EnzoError};
+use git2::{build::RepoBuilder, ObjectType, Oid, Repository};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MIRROR_REFSPEC: &str = "+refs/*:refs/*";
+
+/// A bare mirror of a template repo kept in the local cache.
+#[derive(Debug)]
+pub struct CachedTemplate {
+    pub path: PathBuf,
+    pub url: Option<String>,
+}
+
+/// Where the mirror of `url` lives inside `cache`. The name is readable but
+/// lossy, so it ends with a short hash of the whole url to keep different urls
+/// apart.
+pub fn mirror_path(cache: &Path, url: &str) -> PathBuf {
+    let key = url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .split("://")
+        .last()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let hash = Oid::hash_object(ObjectType::Blob, url.as_bytes())
+        .map(|oid| oid.to_string()[..8].to_string())
+        .unwrap_or_default();
+    cache.join(format!("{}-{}.git", key.trim_start_matches('_'), hash))
+}
+
+/// Makes sure an up to date mirror of `url` exists in `cache` and returns its
+/// path. If the mirror exists but cannot be refreshed, e.g. because there is
+/// no network, the cached copy is used as is.
+pub fn mirror(cache: &Path, url: &str, ssh_keys: &[PathBuf]) -> Result<PathBuf, EnzoError> {
+    let path = mirror_path(cache, url);
+    if path.exists() {
+        if let Err(e) = refresh(&path, ssh_keys) {
+            utils::warning(format!(
+                "Could not refresh the cached template, using the cached copy instead: {}",
+                e
+            ));
+        }
+        return Ok(path);
+    }
+
+    utils::info(format!("caching template {}", url));
+    fs::create_dir_all(cache)?;
+    let auth = Auth::new(ssh_keys);
+    let reporter = Reporter::new();
+    let mut builder = RepoBuilder::new();
+    builder
+        .bare(true)
+        .fetch_options(fetch_options(&auth, &reporter, None))
+        .remote_create(|repo, name, url| repo.remote_with_fetch(name, url, MIRROR_REFSPEC));
+    let res = builder.clone(url, &path);
+    reporter.finish();
+    auth.report(&res);
+    if let Err(e) = res {
+        // don't leave a half-written mirror behind to be mistaken for a cached copy
+        let _ = fs::remove_dir_all(&path);
+        return Err(e.into());
+    }
+    Ok(path)
+}
+
+/// Fetches everything from the mirror's origin.
+pub fn refresh(path: &Path, ssh_keys: &[PathBuf]) -> Result<(), EnzoError> {
+    let repo = Repository::open_bare(path)?;
+    let mut remote = repo.find_remote("origin")?;
+    utils::info(format!(
+        "refreshing cached template {}",
+        remote.url().unwrap_or_default()
+    ));
+    let auth = Auth::new(ssh_keys);
+    let reporter = Reporter::new();
+    let mut fo = fetch_options(&auth, &reporter, None);
+    fo.prune(git2::FetchPrune::On);
+    let res = remote.fetch(&[MIRROR_REFSPEC], Some(&mut fo), None);
+    reporter.finish();
+    auth.report(&res);
+    res?;
+    Ok(())
+}
+
+pub fn list(cache: &Path) -> Result<Vec<CachedTemplate>, EnzoError> {
+    let mut templates = vec![];
+    if !cache.exists() {
+        return Ok(templates);
+    }
+    for entry in fs::read_dir(cache)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let url = Repository::open_bare(&path).ok().and_then(|repo| {
+            repo.find_remote("origin")
+                .ok()
+                .and_then(|remote| remote.url().map(String::from))
+        });
+        templates.push(CachedTemplate { path, url });
+    }
+    templates.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mirror_paths_of_similar_urls() {
+        let cache = Path::new("cache");
+        let a = mirror_path(cache, "https://example.com/a/b-c.git");
+        let b = mirror_path(cache, "https://example.com/a-b/c.git");
+        assert_ne!(a, b);
+        assert!(a.to_string_lossy().starts_with("cache/example.com_a_b-c-"));
+        assert_eq!(a, mirror_path(cache, "https://example.com/a/b-c.git"));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::source::Source;
+use crate::utils::error::{EnzoError, EnzoErrorKind};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// A credential as described by git's credential helper protocol, see
+/// `git help credential`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Credential {
+    pub protocol: Option<String>,
+    pub host: Option<String>,
+    pub path: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Credential {
+    pub fn for_url(url: &str) -> Self {
+        let source = Source::parse(url);
+        Credential {
+            protocol: Some(source.protocol),
+            host: source.host,
+            path: Some(source.path).filter(|path| !path.is_empty()),
+            ..Default::default()
+        }
+    }
+
+    /// Parses the `key=value` lines written by `git credential fill`. Keys may
+    /// come in any order and unknown keys are ignored. A `url` key is broken
+    /// down into its protocol, host and path.
+    pub fn parse(input: &str) -> Self {
+        let mut credential = Credential::default();
+        for line in input.lines() {
+            if line.is_empty() {
+                break;
+            }
+            let (key, value) = match line.find('=') {
+                Some(i) => (&line[..i], line[i + 1..].to_string()),
+                None => continue,
+            };
+            match key {
+                "protocol" => credential.protocol = Some(value),
+                "host" => credential.host = Some(value),
+                "path" => credential.path = Some(value),
+                "username" => credential.username = Some(value),
+                "password" => credential.password = Some(value),
+                "url" => {
+                    let Credential {
+                        protocol,
+                        host,
+                        path,
+                        ..
+                    } = Credential::for_url(&value);
+                    credential.protocol = protocol;
+                    credential.host = host;
+                    credential.path = path;
+                }
+                _ => {}
+            }
+        }
+        credential
+    }
+
+    pub fn userpass(&self) -> Option<(&str, &str)> {
+        match (&self.username, &self.password) {
+            (Some(username), Some(password)) => Some((username, password)),
+            _ => None,
+        }
+    }
+
+    /// Asks the configured credential helpers (and, failing that, the user) for
+    /// the missing username and password.
+    pub fn fill(&self) -> Result<Credential, EnzoError> {
+        let output = self.run("fill", true)?;
+        Ok(Credential::parse(&output))
+    }
+
+    /// Like `fill`, but only what the helpers have stored is used. git does
+    /// not ask the user for anything that is missing.
+    pub fn fill_without_prompt(&self) -> Result<Credential, EnzoError> {
+        let output = self.run("fill", false)?;
+        Ok(Credential::parse(&output))
+    }
+
+    /// Tells the credential helpers that this credential worked so it can be
+    /// stored.
+    pub fn approve(&self) -> Result<(), EnzoError> {
+        self.run("approve", false).map(|_| ())
+    }
+
+    /// Tells the credential helpers that this credential was rejected so a
+    /// stale entry gets erased.
+    pub fn reject(&self) -> Result<(), EnzoError> {
+        self.run("reject", false).map(|_| ())
+    }
+
+    fn run(&self, action: &str, prompt: bool) -> Result<String, EnzoError> {
+        let error = |e: std::io::Error| {
+            EnzoError::new(
+                format!("Failed to run `git credential {}`: {}", action, e),
+                EnzoErrorKind::GitError,
+            )
+        };
+        let mut command = Command::new("git");
+        command
+            .args(["credential", action])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+        if !prompt {
+            command.env("GIT_TERMINAL_PROMPT", "0");
+        }
+        let mut child = command.spawn().map_err(error)?;
+        if let Some(stdin) = child.stdin.as_mut() {
+            stdin
+                .write_all(self.to_string().as_bytes())
+                .map_err(error)?;
+        }
+        let output = child.wait_with_output().map_err(error)?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(EnzoError::new(
+                format!("`git credential {}` exited with {}", action, output.status),
+                EnzoErrorKind::GitError,
+            ))
+        }
+    }
+}
+
+impl std::fmt::Display for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields = [
+            ("protocol", &self.protocol),
+            ("host", &self.host),
+            ("path", &self.path),
+            ("username", &self.username),
+            ("password", &self.password),
+        ];
+        for (key, value) in fields.iter() {
+            if let Some(value) = value {
+                writeln!(f, "{}={}", key, value)?;
+            }
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_any_order() {
+        let credential = Credential::parse(
+            "password=hunter2\nhost=gitlab.com\nusername=elton\nprotocol=https\npath=team/repo.git\n\n",
+        );
+        assert_eq!(
+            credential,
+            Credential {
+                protocol: Some("https".into()),
+                host: Some("gitlab.com".into()),
+                path: Some("team/repo.git".into()),
+                username: Some("elton".into()),
+                password: Some("hunter2".into()),
+            }
+        );
+        assert_eq!(credential.userpass(), Some(("elton", "hunter2")));
+    }
+
+    #[test]
+    fn parse_url_and_unknown_keys() {
+        let credential = Credential::parse(
+            "url=https://elton@github.com/HackGT/enzo.git\nquit=0\nusername=elton\n",
+        );
+        assert_eq!(credential.protocol, Some("https".into()));
+        assert_eq!(credential.host, Some("github.com".into()));
+        assert_eq!(credential.path, Some("HackGT/enzo.git".into()));
+        assert_eq!(credential.username, Some("elton".into()));
+        assert_eq!(credential.userpass(), None);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let credential = Credential {
+            protocol: Some("https".into()),
+            host: Some("github.com".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            credential.to_string(),
+            "protocol=https\nhost=github.com\n\n"
+        );
+        assert_eq!(Credential::parse(&credential.to_string()), credential);
+    }
+}
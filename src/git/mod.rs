@@ -0,0 +1,324 @@
+mod auth;
+pub mod cache;
+mod credential;
+mod progress;
+mod status;
+mod sync;
+
+use crate::utils::{
+    self,
+    error::{EnzoError, EnzoErrorKind},
+};
+use auth::Auth;
+use git2::{
+    build::RepoBuilder, AutotagOption, FetchOptions, IndexAddOption, Repository,
+    SubmoduleUpdateOptions,
+};
+use progress::Reporter;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub use status::{status, RepoStatus};
+pub use sync::{sync, SyncOutcome};
+
+/// What to check out after cloning and how much history to fetch. At most one
+/// of `branch`, `tag` and `rev` is expected to be set.
+#[derive(Clone, Debug)]
+pub struct CloneOptions {
+    pub branch: Option<String>,
+    pub tag: Option<String>,
+    pub rev: Option<String>,
+    pub depth: Option<i32>,
+    pub submodules: bool,
+    /// url to point `origin` at after cloning, when `src` is only a local copy
+    pub origin: Option<String>,
+}
+
+impl Default for CloneOptions {
+    fn default() -> Self {
+        CloneOptions {
+            branch: None,
+            tag: None,
+            rev: None,
+            depth: None,
+            submodules: true,
+            origin: None,
+        }
+    }
+}
+
+pub fn clone(
+    src: &str,
+    dst: &Path,
+    ssh_keys: &[PathBuf],
+    options: &CloneOptions,
+) -> Result<(), EnzoError> {
+    utils::info("cloning");
+
+    let auth = Auth::new(ssh_keys);
+    let reporter = Reporter::new();
+
+    let mut builder = RepoBuilder::new();
+    builder.fetch_options(fetch_options(&auth, &reporter, options.depth));
+    builder.with_checkout(reporter.checkout());
+    if let Some(branch) = &options.branch {
+        builder.branch(branch);
+    }
+
+    let res = builder.clone(src, dst);
+    reporter.finish();
+    auth.report(&res);
+    let repo = res?;
+
+    if let Some(tag) = &options.tag {
+        let refspec = format!("+refs/tags/{0}:refs/tags/{0}", tag);
+        checkout(
+            &repo,
+            &format!("refs/tags/{}", tag),
+            Some(&refspec),
+            &auth,
+            options,
+        )?;
+    } else if let Some(rev) = &options.rev {
+        // remotes only hand out refs, so a (short) commit id is looked up
+        // locally after fetching everything
+        checkout(&repo, rev, None, &auth, options)?;
+    }
+    // only now, so that the tag or rev comes from `src` too, and before the
+    // submodules, whose relative urls are resolved against origin
+    if let Some(origin) = &options.origin {
+        repo.remote_set_url("origin", origin)?;
+    }
+    if options.submodules {
+        update_submodules(&repo, &auth)?;
+    }
+
+    utils::success("cloned");
+    Ok(())
+}
+
+/// Starts a new history in `target` with a single commit of everything in it,
+/// authored with the user's git identity.
+pub fn init(target: &Path, message: &str) -> Result<(), EnzoError> {
+    let repo = Repository::init(target)?;
+    let signature = match repo.signature() {
+        Ok(signature) => signature,
+        Err(e) => {
+            let msg = format!(
+                "{}\nhint: Set your identity with `git config --global user.name` and `git config --global user.email`",
+                e.message()
+            );
+            return Err(EnzoError::new(msg, EnzoErrorKind::GitError));
+        }
+    };
+
+    let mut index = repo.index()?;
+    index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let commit = repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &[])?;
+    utils::info(format!("created initial commit {}", commit));
+    Ok(())
+}
+
+/// Turns the checkout at `target` into plain files by removing its `.git`,
+/// along with the `.git` files and `.gitmodules` of its submodules, which
+/// would otherwise point into the removed history.
+pub fn remove_history(target: &Path) -> Result<(), EnzoError> {
+    for entry in fs::read_dir(target)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default();
+        if name == ".git" || name == ".gitmodules" {
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        } else if path.is_dir() && !path.symlink_metadata()?.file_type().is_symlink() {
+            remove_history(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The id of the commit HEAD points to, if any.
+pub fn head_commit(path: &Path) -> Result<Option<String>, EnzoError> {
+    let repo = Repository::open(path)?;
+    let head = repo.head().ok().and_then(|head| head.target());
+    Ok(head.map(|oid| oid.to_string()))
+}
+
+/// The url of the `origin` remote, if there is one.
+pub fn origin_url(path: &Path) -> Result<Option<String>, EnzoError> {
+    let repo = Repository::open(path)?;
+    let url = match repo.find_remote("origin") {
+        Ok(remote) => remote.url().map(String::from),
+        Err(_) => None,
+    };
+    Ok(url)
+}
+
+/// Points `origin` at `url`. An existing `origin` is kept around as `template`.
+pub fn set_origin(path: &Path, url: &str) -> Result<(), EnzoError> {
+    let repo = Repository::open(path)?;
+    if repo.find_remote("origin").is_ok() {
+        repo.remote_rename("origin", "template")?;
+    }
+    repo.remote("origin", url)?;
+    Ok(())
+}
+
+/// Initializes and updates every submodule of `repo`, and theirs in turn.
+fn update_submodules(repo: &Repository, auth: &Auth) -> Result<(), EnzoError> {
+    for mut submodule in repo.submodules()? {
+        let name = submodule.name().unwrap_or_default().to_string();
+        utils::info(format!("updating submodule {}", name));
+
+        let reporter = Reporter::new();
+        let mut opts = SubmoduleUpdateOptions::new();
+        opts.fetch(fetch_options(auth, &reporter, None));
+        opts.checkout(reporter.checkout());
+        let res = submodule.update(true, Some(&mut opts));
+        reporter.finish();
+        auth.report(&res);
+        if let Err(e) = res {
+            let msg = format!("Failed to update submodule {}: {}", name, e.message());
+            return Err(EnzoError::new(msg, EnzoErrorKind::GitError));
+        }
+
+        update_submodules(&submodule.open()?, auth)?;
+    }
+    Ok(())
+}
+
+fn fetch_options<'a>(auth: &Auth, reporter: &Reporter, depth: Option<i32>) -> FetchOptions<'a> {
+    let mut callbacks = auth.callbacks();
+    reporter.attach(&mut callbacks);
+    let mut fo = FetchOptions::new();
+    fo.remote_callbacks(callbacks);
+    if let Some(depth) = depth {
+        fo.depth(depth);
+    }
+    fo
+}
+
+/// Detaches HEAD at `spec`. If the clone did not bring it along (e.g. a tag or
+/// a commit outside a shallow history), `refspec` is fetched from origin first,
+/// or all of origin's refs and their full history if there is no refspec.
+fn checkout(
+    repo: &Repository,
+    spec: &str,
+    refspec: Option<&str>,
+    auth: &Auth,
+    options: &CloneOptions,
+) -> Result<(), EnzoError> {
+    let object = match repo.revparse_single(spec) {
+        Ok(object) => object,
+        Err(_) => {
+            utils::info(format!("fetching {}", spec));
+            let reporter = Reporter::new();
+            let (refspecs, depth) = match refspec {
+                Some(refspec) => (vec![refspec], options.depth),
+                // i32::MAX asks libgit2 to unshallow the clone
+                None => (vec![], options.depth.map(|_| i32::MAX)),
+            };
+            let mut fo = fetch_options(auth, &reporter, depth);
+            fo.download_tags(AutotagOption::All);
+            let mut remote = repo.find_remote("origin")?;
+            let res = remote.fetch(&refspecs, Some(&mut fo), None);
+            reporter.finish();
+            auth.report(&res);
+            res?;
+            match repo.revparse_single(spec) {
+                Ok(object) => object,
+                Err(_) => {
+                    let msg = format!(
+                        "Could not find {} in {}",
+                        spec,
+                        remote.url().unwrap_or("origin")
+                    );
+                    return Err(EnzoError::new(msg, EnzoErrorKind::GitError));
+                }
+            }
+        }
+    };
+    let commit = object.peel_to_commit()?;
+    let reporter = Reporter::new();
+    repo.checkout_tree(commit.as_object(), Some(&mut reporter.checkout()))?;
+    reporter.finish();
+    repo.set_head_detached(commit.id())?;
+    utils::info(format!("checked out {} at {}", spec, commit.id()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use git2::Signature;
+
+    fn commit(repo: &Repository, file: &str) -> git2::Oid {
+        let workdir = repo.workdir().unwrap();
+        fs::write(workdir.join(file), file).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("enzo", "enzo@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents = parent.iter().collect::<Vec<_>>();
+        repo.commit(Some("HEAD"), &signature, &signature, file, &tree, &parents)
+            .unwrap()
+    }
+
+    #[test]
+    fn clone_at_short_rev() {
+        let dir = std::env::temp_dir().join(format!("enzo-rev-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let src = Repository::init(dir.join("src")).unwrap();
+        let first = commit(&src, "one");
+        commit(&src, "two");
+
+        let options = CloneOptions {
+            rev: Some(first.to_string()[..7].to_string()),
+            ..Default::default()
+        };
+        let dst = dir.join("dst");
+        clone(src.path().to_str().unwrap(), &dst, &[], &options).unwrap();
+        assert_eq!(head_commit(&dst).unwrap(), Some(first.to_string()));
+        assert!(!dst.join("two").exists());
+
+        let options = CloneOptions {
+            rev: Some("0000000".into()),
+            ..Default::default()
+        };
+        assert!(clone(
+            src.path().to_str().unwrap(),
+            &dir.join("missing"),
+            &[],
+            &options
+        )
+        .is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn remove_history_of_submodules() {
+        let dir = std::env::temp_dir().join(format!("enzo-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).unwrap();
+        commit(&repo, "one");
+        fs::write(dir.join(".gitmodules"), "[submodule \"lib\"]\n").unwrap();
+        fs::create_dir(dir.join("lib")).unwrap();
+        fs::write(dir.join("lib/.git"), "gitdir: ../.git/modules/lib\n").unwrap();
+        fs::write(dir.join("lib/lib.rs"), "").unwrap();
+
+        remove_history(&dir).unwrap();
+        assert!(!dir.join(".git").exists());
+        assert!(!dir.join(".gitmodules").exists());
+        assert!(!dir.join("lib/.git").exists());
+        assert!(dir.join("one").exists() && dir.join("lib/lib.rs").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
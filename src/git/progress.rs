@@ -0,0 +1,162 @@
+use git2::{build::CheckoutBuilder, Progress, RemoteCallbacks};
+use std::cell::RefCell;
+use std::io::{stderr, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+const BAR_WIDTH: usize = 30;
+/// How often a line is printed when stderr is not a terminal.
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reports the transfer and checkout progress of a clone. Progress bars are
+/// redrawn in place on a terminal; otherwise a single line is printed every
+/// few seconds so logs stay readable.
+pub struct Reporter {
+    state: Rc<RefCell<State>>,
+}
+
+struct State {
+    silent: bool,
+    tty: bool,
+    last: Option<(Instant, &'static str)>,
+    drawn: bool,
+}
+
+impl Reporter {
+    pub fn new() -> Self {
+        Reporter {
+            state: Rc::new(RefCell::new(State {
+                silent: false,
+                tty: atty::is(atty::Stream::Stderr),
+                last: None,
+                drawn: false,
+            })),
+        }
+    }
+
+    /// A reporter that swallows all progress.
+    pub fn silent() -> Self {
+        let reporter = Reporter::new();
+        reporter.state.borrow_mut().silent = true;
+        reporter
+    }
+
+    pub fn attach(&self, callbacks: &mut RemoteCallbacks<'_>) {
+        let state = Rc::clone(&self.state);
+        callbacks.transfer_progress(move |progress| {
+            state.borrow_mut().transfer(&progress);
+            true
+        });
+    }
+
+    pub fn checkout<'a>(&self) -> CheckoutBuilder<'a> {
+        let state = Rc::clone(&self.state);
+        let mut checkout = CheckoutBuilder::new();
+        checkout.progress(move |path, current, total| {
+            state.borrow_mut().checkout(path, current, total);
+        });
+        checkout
+    }
+
+    /// Moves past the progress bars so the next message starts on a new line.
+    pub fn finish(&self) {
+        let mut state = self.state.borrow_mut();
+        if state.tty && state.drawn {
+            eprintln!();
+        }
+        state.drawn = false;
+    }
+}
+
+impl State {
+    fn transfer(&mut self, progress: &Progress<'_>) {
+        let received = progress.received_objects();
+        let total = progress.total_objects();
+        let (stage, line) = if received < total || progress.total_deltas() == 0 {
+            (
+                "receiving objects",
+                format!(
+                    "{} {}/{} ({})",
+                    bar(received, total),
+                    received,
+                    total,
+                    human_bytes(progress.received_bytes())
+                ),
+            )
+        } else {
+            let indexed = progress.indexed_deltas();
+            let deltas = progress.total_deltas();
+            (
+                "resolving deltas",
+                format!("{} {}/{}", bar(indexed, deltas), indexed, deltas),
+            )
+        };
+        let done = received == total && progress.indexed_deltas() == progress.total_deltas();
+        self.report(stage, line, done);
+    }
+
+    fn checkout(&mut self, _path: Option<&Path>, current: usize, total: usize) {
+        let percent = (current * 100).checked_div(total).unwrap_or(100);
+        let line = format!(
+            "{} {}% ({}/{})",
+            bar(current, total),
+            percent,
+            current,
+            total
+        );
+        self.report("checking out", line, current == total);
+    }
+
+    fn report(&mut self, stage: &'static str, line: String, done: bool) {
+        if self.silent {
+            return;
+        }
+        let now = Instant::now();
+        let stage_changed = self.last.map(|(_, s)| s != stage).unwrap_or(true);
+        if self.tty {
+            if stage_changed && self.drawn {
+                eprintln!();
+            }
+            eprint!(
+                "\r{} {}",
+                ansi_term::Color::Blue.bold().paint(format!("[{}]", stage)),
+                line
+            );
+            let _ = stderr().flush();
+            self.drawn = true;
+            self.last = Some((now, stage));
+        } else {
+            let due = match self.last {
+                Some((at, _)) => now.duration_since(at) >= LOG_INTERVAL,
+                None => true,
+            };
+            if stage_changed || due || done {
+                eprintln!("[{}] {}", stage, line);
+                self.last = Some((now, stage));
+            }
+        }
+    }
+}
+
+fn bar(current: usize, total: usize) -> String {
+    let filled = (current * BAR_WIDTH)
+        .checked_div(total)
+        .unwrap_or(BAR_WIDTH);
+    format!("[{}{}]", "#".repeat(filled), ".".repeat(BAR_WIDTH - filled))
+}
+
+fn human_bytes(bytes: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
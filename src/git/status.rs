@@ -0,0 +1,55 @@
+use crate::utils::error::EnzoError;
+use git2::{BranchType, Repository, Status, StatusOptions};
+use serde::Serialize;
+use std::path::Path;
+
+/// A summary of the state of a repo's working tree and current branch.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RepoStatus {
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub dirty: usize,
+    pub untracked: usize,
+    pub stashes: usize,
+}
+
+pub fn status(path: &Path) -> Result<RepoStatus, EnzoError> {
+    let mut repo = Repository::open(path)?;
+    let mut status = RepoStatus::default();
+
+    if let Ok(head) = repo.head() {
+        if head.is_branch() {
+            status.branch = head.shorthand().map(String::from);
+            let branch =
+                repo.find_branch(head.shorthand().unwrap_or_default(), BranchType::Local)?;
+            if let Ok(upstream) = branch.upstream() {
+                status.upstream = upstream.name()?.map(String::from);
+                if let (Some(local), Some(remote)) = (head.target(), upstream.get().target()) {
+                    let (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+                    status.ahead = ahead;
+                    status.behind = behind;
+                }
+            }
+        }
+    }
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).exclude_submodules(true);
+    for entry in repo.statuses(Some(&mut opts))?.iter() {
+        let s = entry.status();
+        if s.contains(Status::WT_NEW) {
+            status.untracked += 1;
+        } else if !s.is_empty() && !s.contains(Status::IGNORED) {
+            status.dirty += 1;
+        }
+    }
+
+    repo.stash_foreach(|_, _, _| {
+        status.stashes += 1;
+        true
+    })?;
+
+    Ok(status)
+}
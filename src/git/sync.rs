@@ -0,0 +1,87 @@
+use super::{auth::Auth, fetch_options, progress::Reporter};
+use crate::utils::error::EnzoError;
+use git2::{build::CheckoutBuilder, Repository, Status, StatusOptions};
+use std::path::{Path, PathBuf};
+
+/// What happened to a single repo during a sync.
+#[derive(Debug)]
+pub enum SyncOutcome {
+    Updated {
+        branch: String,
+        from: String,
+        to: String,
+    },
+    UpToDate,
+    Skipped(String),
+    Failed(String),
+}
+
+/// Fetches the upstream of the current branch and fast-forwards to it when the
+/// working tree is clean and the branch has not diverged.
+pub fn sync(path: &Path, ssh_keys: &[PathBuf]) -> SyncOutcome {
+    match try_sync(path, ssh_keys) {
+        Ok(outcome) => outcome,
+        Err(e) => SyncOutcome::Failed(e.to_string()),
+    }
+}
+
+fn try_sync(path: &Path, ssh_keys: &[PathBuf]) -> Result<SyncOutcome, EnzoError> {
+    let repo = Repository::open(path)?;
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Ok(SyncOutcome::Skipped("HEAD is detached".into()));
+    }
+    let refname = head.name().unwrap_or_default().to_string();
+    let branch = head.shorthand().unwrap_or_default().to_string();
+    let remote_name = match repo.branch_upstream_remote(&refname) {
+        Ok(buf) => buf.as_str().unwrap_or_default().to_string(),
+        Err(_) => return Ok(SyncOutcome::Skipped(format!("{} has no upstream", branch))),
+    };
+
+    // several repos are synced at once, so nobody can be asked for a passphrase
+    let auth = Auth::non_interactive(ssh_keys);
+    // progress bars from concurrent syncs would trample each other, so none are drawn
+    let reporter = Reporter::silent();
+    let mut fo = fetch_options(&auth, &reporter, None);
+    let mut remote = repo.find_remote(&remote_name)?;
+    let res = remote.fetch::<&str>(&[], Some(&mut fo), None);
+    auth.report(&res);
+    res?;
+
+    let local = repo.find_branch(&branch, git2::BranchType::Local)?;
+    let upstream = local.upstream()?;
+    let (local_oid, upstream_oid) = match (head.target(), upstream.get().target()) {
+        (Some(l), Some(u)) => (l, u),
+        _ => return Ok(SyncOutcome::Skipped("could not resolve upstream".into())),
+    };
+    if local_oid == upstream_oid || repo.graph_descendant_of(local_oid, upstream_oid)? {
+        return Ok(SyncOutcome::UpToDate);
+    }
+    if !repo.graph_descendant_of(upstream_oid, local_oid)? {
+        return Ok(SyncOutcome::Skipped(format!("{} has diverged", branch)));
+    }
+    if is_dirty(&repo)? {
+        return Ok(SyncOutcome::Skipped("working tree is dirty".into()));
+    }
+
+    let target = repo.find_object(upstream_oid, None)?;
+    repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+    repo.find_reference(&refname)?
+        .set_target(upstream_oid, "enzo sync: fast-forward")?;
+
+    Ok(SyncOutcome::Updated {
+        branch,
+        from: local_oid.to_string()[..7].to_string(),
+        to: upstream_oid.to_string()[..7].to_string(),
+    })
+}
+
+fn is_dirty(repo: &Repository) -> Result<bool, EnzoError> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).exclude_submodules(true);
+    let dirty = repo
+        .statuses(Some(&mut opts))?
+        .iter()
+        .any(|entry| !entry.status().is_empty() && !entry.status().contains(Status::IGNORED));
+    Ok(dirty)
+}
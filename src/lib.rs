@@ -1,20 +1,239 @@
 pub mod config;
+mod doctor;
 mod git;
+mod shell;
+mod source;
 mod todos;
 pub mod utils;
 pub mod workspace;
 
+pub use git::CloneOptions;
+
 use config::{global::Config, section::ExecutionContext};
+use serde::Serialize;
+use source::Source;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use todos::sync::Side;
 use utils::error::{EnzoError, EnzoErrorKind};
 use utils::query::{AnswerKind, Question};
-use workspace::{project::Project, WorkspaceName};
+use workspace::{
+    project::{Project, TodoStore},
+    WorkspaceData, WorkspaceName,
+};
+
+pub fn resolve_src(config: &Config, src: &str) -> Result<Source, EnzoError> {
+    let url = if source::is_explicit(src) {
+        resolve_local(src)?
+    } else if let Some((alias, path)) = source::split_alias(src) {
+        match config.get_host(alias) {
+            Some(base) => source::expand(base, path),
+            // not an alias, so treat it as an scp-style `host:path` address
+            None => src.to_string(),
+        }
+    } else if src.matches('/').count() == 1 {
+        source::expand("https://github.com", src)
+    } else {
+        let msg = format!(
+            "Expected `src` to be a url, a path, <alias>:<path> or <username>/<repo_name>. Found: {}",
+            src
+        );
+        return Err(EnzoError::new(msg, EnzoErrorKind::FatalError));
+    };
+    utils::info(format!("src = {}", url));
+    Ok(Source::parse(url))
+}
+
+fn resolve_local(src: &str) -> Result<String, EnzoError> {
+    let path = if let Some(rest) = src.strip_prefix('~') {
+        let mut home = utils::get_home_dir()?;
+        home.push(rest.trim_start_matches('/'));
+        home
+    } else {
+        PathBuf::from(src)
+    };
+    if path.exists() {
+        Ok(path.canonicalize()?.to_string_lossy().into_owned())
+    } else {
+        Ok(src.to_string())
+    }
+}
+
+pub fn add_workspace(
+    config: &mut Config,
+    name: WorkspaceName,
+    data: WorkspaceData,
+) -> Result<(), EnzoError> {
+    let nested = nested_workspaces(config, &data.path);
+    let projects = data
+        .projects
+        .into_iter()
+        .filter(|(path, _)| !nested.iter().any(|n| path.starts_with(n)))
+        .collect();
+    config.add_workspace(name, data.path);
+    register_projects(config, projects);
+    Ok(())
+}
+
+pub fn scan(config: &mut Config, workspace: &str) -> Result<(), EnzoError> {
+    let (name, root) = resolve_dst(config, workspace)?;
+    let nested = nested_workspaces(config, &root);
+    let projects = workspace::scan(&name, &root, &nested)?;
+    register_projects(config, projects);
+    Ok(())
+}
+
+/// The paths of the workspaces that live inside `root`, whose repos belong to
+/// them rather than to the workspace at `root`.
+fn nested_workspaces(config: &Config, root: &Path) -> Vec<PathBuf> {
+    config
+        .workspaces()
+        .map(|(_, path)| path)
+        .filter(|path| path != root && path.starts_with(root))
+        .collect()
+}
 
-pub fn resolve_src(src: &str) -> Result<String, EnzoError> {
-    let src = format!("{}/{}.git", "https://github.com", src);
-    utils::info(format!("src = {}", src));
-    Ok(src)
+/// Adds the projects that are not registered yet. Projects enzo already knows
+/// about keep their todos.
+fn register_projects(config: &mut Config, projects: Vec<(PathBuf, Project)>) {
+    let found = projects.len();
+    let mut added = 0;
+    for (path, project) in projects {
+        if config.get_project(&path).is_none() {
+            utils::info(format!("found {}", path.to_string_lossy()));
+            config.add_project(path, project);
+            added += 1;
+        }
+    }
+    utils::success(format!(
+        "found {} repos, {} of them new to enzo",
+        found, added
+    ));
+}
+
+pub fn remove_workspace(config: &mut Config, name: &str) -> Result<(), EnzoError> {
+    let name = WorkspaceName::from(name);
+    if config.remove_workspace(&name).is_none() {
+        let msg = format!("There is no workspace called {}", name.0);
+        return Err(EnzoError::new(msg, EnzoErrorKind::ConfigError));
+    }
+    let projects = config
+        .projects()
+        .filter(|(_, project)| project.workspace() == &name)
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    for path in &projects {
+        config.remove_project(path);
+    }
+    utils::success(format!(
+        "removed workspace {} and {} of its projects. Nothing was deleted from disk",
+        name.0,
+        projects.len()
+    ));
+    Ok(())
+}
+
+pub fn remove_project(config: &mut Config, src: Option<&str>) -> Result<(), EnzoError> {
+    let path = match src {
+        Some(src) => resolve_dst(config, src)?.1,
+        None => std::env::current_dir()?,
+    };
+    match config.remove_project(&path) {
+        Some(project) => {
+            utils::success(format!(
+                "removed project {}. Nothing was deleted from disk",
+                project.name()
+            ));
+            Ok(())
+        }
+        None => Err(EnzoError::new(
+            format!("The project at {:?} does not exist", path),
+            EnzoErrorKind::ConfigError,
+        )),
+    }
+}
+
+pub fn rename_workspace(config: &mut Config, from: &str, to: &str) -> Result<(), EnzoError> {
+    let to = WorkspaceName::from(to);
+    let from_name = WorkspaceName::from(from);
+    // workspaces nested under `from` are renamed along with it
+    let renamed = config
+        .workspaces()
+        .filter_map(|(name, _)| name.renamed(&from_name, &to))
+        .collect::<Vec<_>>();
+    let taken = config
+        .workspaces()
+        .find(|(name, _)| name.renamed(&from_name, &to).is_none() && renamed.contains(name));
+    if let Some((name, _)) = taken {
+        let msg = format!("There already is a workspace called {}", name.0);
+        return Err(EnzoError::new(msg, EnzoErrorKind::ConfigError));
+    }
+    match config.rename_workspace(&from_name, to.clone()) {
+        Some(_) => {
+            utils::success(format!("renamed workspace {} to {}", from, to.0));
+            Ok(())
+        }
+        None => Err(EnzoError::new(
+            format!("There is no workspace called {}", from),
+            EnzoErrorKind::ConfigError,
+        )),
+    }
+}
+
+/// Moves a project on disk and in the config. Like `mv`, a `dst` that is an
+/// existing directory receives the project under its current name.
+pub fn move_project(config: &mut Config, src: &str, dst: &str) -> Result<(), EnzoError> {
+    let (_, from) = resolve_dst(config, src)?;
+    if config.get_project(&from).is_none() {
+        return Err(EnzoError::new(
+            format!("The project at {:?} does not exist", from),
+            EnzoErrorKind::ConfigError,
+        ));
+    }
+    let (workspace, mut to) = resolve_dst(config, dst)?;
+    if to.is_dir() {
+        if let Some(name) = from.file_name() {
+            to.push(name);
+        }
+    }
+    if to.exists() {
+        return Err(EnzoError::new(
+            format!("{:?} already exists", to),
+            EnzoErrorKind::IOError,
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    utils::move_dir(&from, &to)?;
+    config.move_project(&from, &to, &workspace);
+    utils::success(format!(
+        "moved {} to {}",
+        from.to_string_lossy(),
+        to.to_string_lossy()
+    ));
+    Ok(())
+}
+
+pub fn add_host(config: &mut Config) -> Result<(), EnzoError> {
+    let mut alias = AnswerKind::Single(String::new());
+    Question::new_question("Host alias").ask(&mut alias);
+    let mut base = AnswerKind::Single(String::new());
+    Question::new(
+        "Base url",
+        None,
+        None,
+        Some(vec!["https://gitlab.com", "git@gitlab.com:"]),
+    )
+    .ask(&mut base);
+    match (alias, base) {
+        (AnswerKind::Single(alias), AnswerKind::Single(base)) => {
+            utils::info(format!("{}:<path> now resolves to {}", alias, base));
+            config.add_host(alias, base);
+            Ok(())
+        }
+        _ => unreachable!(),
+    }
 }
 
 pub fn resolve_dst(config: &mut Config, dst: &str) -> Result<(WorkspaceName, PathBuf), EnzoError> {
@@ -32,41 +251,64 @@ pub fn clone(
     src: &str,
     dst: &str,
     name: Option<String>,
+    options: &CloneOptions,
 ) -> Result<(), EnzoError> {
+    let src = resolve_src(config, src)?;
     let repo_name = if let Some(name) = name {
         name
     } else {
-        match get_repo_name(&src) {
+        match src.repo_name() {
             Some(name) => name.to_string(),
             None => {
-                let msg = format!(
-                    "Expected `src` to be of the form <username>/<repo_name>. Found: {}",
-                    src
-                );
+                let msg = format!("Could not determine a repo name from {}", src.url);
                 return Err(EnzoError::new(msg, EnzoErrorKind::FatalError));
             }
         }
     };
-    let src = resolve_src(src)?;
     let (workspace_name, mut dst) = resolve_dst(config, dst)?;
     dst.push(repo_name);
     utils::info(format!("dst = {:?}", dst));
 
-    git::clone(&src, &dst)?;
+    git::clone(&src.url, &dst, config.ssh_keys(), options)?;
 
-    dst.push("enzo.yaml");
-    let todos = todos::read_from(&dst)?;
+    // most repos don't have an enzo.yaml, which just means there is nothing to do yet
+    let file = dst.join("enzo.yaml");
+    let todos = if file.exists() {
+        todos::read_from(&file)?
+    } else {
+        vec![]
+    };
 
-    dst.pop();
     // TODO handle error more gracefully
     let name = dst.file_name().unwrap();
-    let project = Project::new(name.to_str().unwrap().into(), workspace_name, src, todos);
+    let project = Project::new(
+        name.to_str().unwrap().into(),
+        workspace_name,
+        src.url,
+        todos,
+    );
     config.add_project(dst, project);
     Ok(())
 }
 
-// TODO clean up
-pub fn new(config: &mut Config, src: &str, dst: &str) -> Result<(), EnzoError> {
+/// How `enzo new` turns a clone of the template into a project of its own.
+#[derive(Clone, Debug, Default)]
+pub struct NewOptions {
+    /// keep the template's history instead of starting a new one
+    pub keep_history: bool,
+    /// mention the template's url and commit in the initial commit message
+    pub record_template: bool,
+    /// remote to set as `origin` of the new project
+    pub origin: Option<String>,
+}
+
+pub fn new(
+    config: &mut Config,
+    src: &str,
+    dst: &str,
+    options: &CloneOptions,
+    new_options: &NewOptions,
+) -> Result<(), EnzoError> {
     let question = Question::new_question("What is the name of your project?");
     let mut answer_kind = AnswerKind::Single(String::new());
     question.ask(&mut answer_kind);
@@ -74,39 +316,703 @@ pub fn new(config: &mut Config, src: &str, dst: &str) -> Result<(), EnzoError> {
         AnswerKind::Single(s) => s.clone(),
         _ => unreachable!(),
     };
-    clone(config, src, dst, Some(name.clone()))?;
+    let origin = match &new_options.origin {
+        Some(origin) => Some(resolve_src(config, origin)?.url),
+        None => None,
+    };
+    let template = resolve_src(config, src)?;
+    let mirror = git::cache::mirror(&template_cache()?, &template.url, config.ssh_keys())?;
+    let options = CloneOptions {
+        origin: Some(template.url.clone()),
+        ..options.clone()
+    };
+    clone(
+        config,
+        &mirror.to_string_lossy(),
+        dst,
+        Some(name.clone()),
+        &options,
+    )?;
 
     let (_, mut dst) = resolve_dst(config, dst)?;
     dst.push(name);
-    dst.push(".git");
-    fs::remove_dir_all(&dst)?;
-    dst.pop();
-    git::init(&dst)?;
+
+    if !new_options.keep_history {
+        let commit = git::head_commit(&dst)?;
+        git::remove_history(&dst)?;
+
+        let mut message = String::from("Initial commit");
+        if new_options.record_template {
+            message.push_str(&format!(
+                "\n\nCreated from template {} at {}",
+                template.url,
+                commit.unwrap_or_else(|| "an unknown commit".into())
+            ));
+        }
+        git::init(&dst, &message)?;
+    }
+
+    if let Some(origin) = origin {
+        git::set_origin(&dst, &origin)?;
+        utils::info(format!("origin = {}", origin));
+    }
+    if let Some(project) = config.get_project_mut(&dst) {
+        project.src = git::origin_url(&dst)?.unwrap_or(template.url);
+    }
     Ok(())
 }
 
-pub fn start_task_manager<'a>(config: &'a mut Config, src: Option<&str>) -> Result<(), EnzoError> {
-    let path = if let Some(src) = src {
-        let (_, dst) = resolve_dst(config, src)?;
-        utils::info(format!("src = {:?}", dst));
-        dst
+fn template_cache() -> Result<PathBuf, EnzoError> {
+    let mut path = utils::get_data_dir()?;
+    path.push("templates");
+    Ok(path)
+}
+
+pub fn list_templates() -> Result<(), EnzoError> {
+    let templates = git::cache::list(&template_cache()?)?;
+    if templates.is_empty() {
+        utils::info("no cached templates");
+    }
+    for template in templates {
+        println!(
+            "{} {}",
+            template.url.unwrap_or_else(|| "(unknown)".into()),
+            ansi_term::Color::White
+                .dimmed()
+                .paint(template.path.to_string_lossy())
+        );
+    }
+    Ok(())
+}
+
+pub fn refresh_templates(config: &Config, src: Option<&str>) -> Result<(), EnzoError> {
+    let cache = template_cache()?;
+    let paths = match src {
+        Some(src) => vec![git::cache::mirror_path(
+            &cache,
+            &resolve_src(config, src)?.url,
+        )],
+        None => git::cache::list(&cache)?
+            .into_iter()
+            .map(|template| template.path)
+            .collect(),
+    };
+    for path in paths {
+        if !path.exists() {
+            let msg = format!("The template at {:?} is not cached", path);
+            return Err(EnzoError::new(msg, EnzoErrorKind::PathDoesNotExist));
+        }
+        git::cache::refresh(&path, config.ssh_keys())?;
+    }
+    utils::success("refreshed templates");
+    Ok(())
+}
+
+pub fn prune_templates(config: &Config, src: Option<&str>) -> Result<(), EnzoError> {
+    let cache = template_cache()?;
+    let paths = match src {
+        Some(src) => vec![git::cache::mirror_path(
+            &cache,
+            &resolve_src(config, src)?.url,
+        )],
+        None => git::cache::list(&cache)?
+            .into_iter()
+            .map(|template| template.path)
+            .collect(),
+    };
+    for path in paths {
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+            utils::info(format!("removed {:?}", path));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct WorkspaceEntry {
+    name: String,
+    path: PathBuf,
+    projects: Vec<ProjectEntry>,
+    workspaces: Vec<WorkspaceEntry>,
+}
+
+#[derive(Serialize)]
+struct ProjectEntry {
+    name: String,
+    path: PathBuf,
+    src: String,
+    open_todos: usize,
+    todos: usize,
+}
+
+pub fn list(config: &Config, json: bool) -> Result<(), EnzoError> {
+    let mut names = config
+        .workspaces()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    names.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let roots = names
+        .iter()
+        .filter(|name| parent_workspace(name, &names).is_none())
+        .map(|name| workspace_entry(config, name, &names))
+        .collect::<Vec<_>>();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&roots)?);
+    } else if roots.is_empty() {
+        utils::info("no workspaces yet. hint: Try creating one with `enzo add workspace`");
     } else {
-        std::env::current_dir()?
+        for root in &roots {
+            print_workspace(root, "", None);
+        }
+    }
+    Ok(())
+}
+
+/// The most specific workspace that `name` is nested under.
+fn parent_workspace<'a>(
+    name: &WorkspaceName,
+    names: &'a [WorkspaceName],
+) -> Option<&'a WorkspaceName> {
+    names
+        .iter()
+        .filter(|other| name.is_nested_in(other))
+        .max_by_key(|other| other.0.len())
+}
+
+fn workspace_entry(
+    config: &Config,
+    name: &WorkspaceName,
+    names: &[WorkspaceName],
+) -> WorkspaceEntry {
+    let path = config.get_path(name).unwrap_or_default();
+    let mut projects = config
+        .projects()
+        .filter(|(_, project)| project.workspace() == name)
+        .map(|(project_path, project)| ProjectEntry {
+            name: project.name().to_string(),
+            path: project_path.clone(),
+            src: project.src.clone(),
+            open_todos: project.todos.iter().filter(|t| !t.is_complete()).count(),
+            todos: project.todos.len(),
+        })
+        .collect::<Vec<_>>();
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+    let workspaces = names
+        .iter()
+        .filter(|other| parent_workspace(other, names) == Some(name))
+        .map(|other| workspace_entry(config, other, names))
+        .collect();
+    WorkspaceEntry {
+        name: name.0.clone(),
+        path,
+        projects,
+        workspaces,
+    }
+}
+
+/// Prints `entry` and everything under it like `tree` does. `last` is `None`
+/// for the top level and otherwise whether `entry` is its parent's last child.
+fn print_workspace(entry: &WorkspaceEntry, prefix: &str, last: Option<bool>) {
+    let (branch, child_prefix) = match last {
+        None => ("", prefix.to_string()),
+        Some(true) => ("└── ", format!("{}    ", prefix)),
+        Some(false) => ("├── ", format!("{}│   ", prefix)),
     };
-    let project = match config.get_project_mut(&path) {
-        Some(project) => project,
+    println!(
+        "{}{}{} {}",
+        prefix,
+        branch,
+        ansi_term::Color::Purple.bold().paint(&entry.name),
+        ansi_term::Color::White
+            .dimmed()
+            .paint(entry.path.to_string_lossy())
+    );
+
+    let children = entry.projects.len() + entry.workspaces.len();
+    for (i, project) in entry.projects.iter().enumerate() {
+        let connector = if i + 1 == children {
+            "└── "
+        } else {
+            "├── "
+        };
+        let name = match project.path.strip_prefix(&entry.path) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.to_string_lossy(),
+            _ => project.name.as_str().into(),
+        };
+        let todos = if project.open_todos > 0 {
+            ansi_term::Color::Yellow
+                .paint(format!(
+                    " ({}/{} todos open)",
+                    project.open_todos, project.todos
+                ))
+                .to_string()
+        } else {
+            String::new()
+        };
+        println!(
+            "{}{}{} {}{}",
+            child_prefix,
+            connector,
+            name,
+            ansi_term::Color::White.dimmed().paint(&project.src),
+            todos
+        );
+    }
+    for (i, workspace) in entry.workspaces.iter().enumerate() {
+        let last = entry.projects.len() + i + 1 == children;
+        print_workspace(workspace, &child_prefix, Some(last));
+    }
+}
+
+#[derive(Serialize)]
+struct ProjectStatus {
+    name: String,
+    path: PathBuf,
+    #[serde(flatten)]
+    status: Option<git::RepoStatus>,
+    error: Option<String>,
+}
+
+pub fn status(config: &mut Config, workspace: Option<&str>, json: bool) -> Result<(), EnzoError> {
+    let root = match workspace {
+        Some(workspace) => Some(resolve_dst(config, workspace)?.1),
+        None => None,
+    };
+    let mut projects = config
+        .projects()
+        .filter(|(path, _)| root.as_ref().map(|r| path.starts_with(r)).unwrap_or(true))
+        .collect::<Vec<_>>();
+    projects.sort_by(|a, b| a.0.cmp(b.0));
+
+    let statuses = projects
+        .into_iter()
+        .map(|(path, project)| {
+            let name = match root.as_ref().and_then(|r| path.strip_prefix(r).ok()) {
+                Some(relative) if !relative.as_os_str().is_empty() => {
+                    relative.to_string_lossy().into_owned()
+                }
+                _ => project.name().to_string(),
+            };
+            let (status, error) = match git::status(path) {
+                Ok(status) => (Some(status), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            ProjectStatus {
+                name,
+                path: path.clone(),
+                status,
+                error,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+    } else {
+        print_status_table(&statuses);
+    }
+    Ok(())
+}
+
+fn print_status_table(statuses: &[ProjectStatus]) {
+    if statuses.is_empty() {
+        utils::info("no projects found");
+        return;
+    }
+    let rows = statuses
+        .iter()
+        .map(|s| match &s.status {
+            Some(status) => [
+                s.name.clone(),
+                status.branch.clone().unwrap_or_else(|| "(detached)".into()),
+                if status.upstream.is_some() {
+                    format!("+{} -{}", status.ahead, status.behind)
+                } else {
+                    "-".into()
+                },
+                status.dirty.to_string(),
+                status.untracked.to_string(),
+                status.stashes.to_string(),
+            ],
+            None => [
+                s.name.clone(),
+                "(error)".into(),
+                "-".into(),
+                "-".into(),
+                "-".into(),
+                "-".into(),
+            ],
+        })
+        .collect::<Vec<_>>();
+    let header = [
+        "project",
+        "branch",
+        "ahead/behind",
+        "dirty",
+        "untracked",
+        "stash",
+    ];
+    let mut widths = header.iter().map(|h| h.len()).collect::<Vec<_>>();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = header
+        .iter()
+        .zip(&widths)
+        .map(|(cell, width)| format!("{:<1$}", cell, width))
+        .collect::<Vec<_>>()
+        .join("  ")
+        .trim_end()
+        .to_string();
+    println!("{}", ansi_term::Color::White.bold().paint(line));
+    for (row, status) in rows.iter().zip(statuses) {
+        let clean = status
+            .status
+            .as_ref()
+            .map(|s| s.dirty == 0 && s.untracked == 0 && s.ahead == 0 && s.behind == 0)
+            .unwrap_or(false);
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<1$}", cell, width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string();
+        if status.error.is_some() {
+            println!("{}", ansi_term::Color::Red.paint(line));
+        } else if clean {
+            println!("{}", line);
+        } else {
+            println!("{}", ansi_term::Color::Yellow.paint(line));
+        }
+    }
+    for status in statuses {
+        if let Some(e) = &status.error {
+            eprintln!("{}: {}", status.name, e);
+        }
+    }
+}
+
+pub fn sync(config: &mut Config, workspace: &str) -> Result<(), EnzoError> {
+    let (_, root) = resolve_dst(config, workspace)?;
+    let mut paths = config
+        .projects()
+        .filter(|(path, _)| path.starts_with(&root))
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    paths.sort();
+    if paths.is_empty() {
+        utils::info("no projects found");
+        return Ok(());
+    }
+
+    utils::info(format!("syncing {} projects", paths.len()));
+    let ssh_keys = config.ssh_keys().to_vec();
+    let mut runtime = tokio::runtime::Runtime::new()?;
+    let outcomes = runtime.block_on(async {
+        let handles = paths.iter().cloned().map(|path| {
+            let ssh_keys = ssh_keys.clone();
+            tokio::task::spawn_blocking(move || git::sync(&path, &ssh_keys))
+        });
+        futures::future::join_all(handles).await
+    });
+
+    let mut updated = vec![];
+    let mut up_to_date = vec![];
+    let mut skipped = vec![];
+    let mut failed = vec![];
+    for (path, outcome) in paths.iter().zip(outcomes) {
+        let name = match path.strip_prefix(&root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.to_string_lossy(),
+            _ => path.to_string_lossy(),
+        };
+        match outcome {
+            Ok(git::SyncOutcome::Updated { branch, from, to }) => {
+                updated.push(format!("{} ({} {}..{})", name, branch, from, to))
+            }
+            Ok(git::SyncOutcome::UpToDate) => up_to_date.push(name.to_string()),
+            Ok(git::SyncOutcome::Skipped(reason)) => skipped.push(format!("{}: {}", name, reason)),
+            Ok(git::SyncOutcome::Failed(e)) => failed.push(format!("{}: {}", name, e)),
+            Err(e) => failed.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    println!();
+    for (title, color, entries) in &[
+        ("updated", ansi_term::Color::Green, &updated),
+        ("up to date", ansi_term::Color::White, &up_to_date),
+        ("skipped", ansi_term::Color::Yellow, &skipped),
+        ("failed", ansi_term::Color::Red, &failed),
+    ] {
+        if entries.is_empty() {
+            continue;
+        }
+        println!("{} ({})", color.bold().paint(*title), entries.len());
+        for entry in entries.iter() {
+            println!("  {}", entry);
+        }
+    }
+    Ok(())
+}
+
+/// Reports problems with the config. With `fix` every problem that can be
+/// fixed is, and with `interactive` the user is asked about each one.
+pub fn doctor(config: &mut Config, fix: bool, interactive: bool) -> Result<(), EnzoError> {
+    let problems = doctor::check(config);
+    if problems.is_empty() {
+        utils::success("no problems found");
+        return Ok(());
+    }
+
+    let mut severity = None;
+    for problem in &problems {
+        if severity != Some(problem.severity) {
+            severity = Some(problem.severity);
+            println!("{}", problem.severity);
+        }
+        match &problem.fix {
+            Some(f) => println!(
+                "  {} {}",
+                problem.message,
+                ansi_term::Color::White
+                    .dimmed()
+                    .paint(format!("(fix: {})", f))
+            ),
+            None => println!("  {}", problem.message),
+        }
+    }
+    println!();
+
+    let mut fixed = 0;
+    for problem in &problems {
+        let f = match &problem.fix {
+            Some(f) => f,
+            None => continue,
+        };
+        let apply = if fix {
+            true
+        } else if interactive {
+            let question = format!("{}. {}?", problem.message, f);
+            let question = Question::new(&question, Some("n"), None, Some(vec!["y/n"]));
+            let mut answer = AnswerKind::Single(String::new());
+            question.ask(&mut answer);
+            match answer {
+                AnswerKind::Single(s) => s.to_lowercase().starts_with('y'),
+                _ => false,
+            }
+        } else {
+            false
+        };
+        if apply {
+            doctor::apply(config, f);
+            fixed += 1;
+        }
+    }
+
+    if fix || interactive {
+        utils::success(format!("fixed {} of {} problems", fixed, problems.len()));
+    } else {
+        utils::info("run `enzo doctor --fix` to fix them all, or `enzo doctor -i` to pick");
+    }
+    Ok(())
+}
+
+/// Prints the shell function and completions for `shell`.
+pub fn shell_init(app: &mut clap::App, shell: &str) -> Result<(), EnzoError> {
+    match shell::init(app, shell) {
+        Some(script) => {
+            println!("{}", script);
+            Ok(())
+        }
         None => {
-            return Err(EnzoError::new(
-                format!("The project at {:?} does not exist", path),
-                EnzoErrorKind::IOError,
-            ))
+            let msg = format!("Expected `bash`, `zsh` or `fish`. Found: {}", shell);
+            Err(EnzoError::new(msg, EnzoErrorKind::FatalError))
+        }
+    }
+}
+
+/// The completions for a workspace name or a path inside a workspace.
+pub fn complete(config: &Config, word: &str) -> Vec<String> {
+    shell::complete(config, word)
+}
+
+pub fn start_task_manager(config: &mut Config, src: Option<&str>) -> Result<(), EnzoError> {
+    let path = project_path(config, src)?;
+    let project = get_project_mut(config, &path)?;
+    let file = path.join("enzo.yaml");
+    let store = project.todo_store();
+    if store == TodoStore::Repo && file.exists() {
+        project.todos = todos::read_from(&file)?;
+    }
+
+    todos::start(&mut project.todos)?;
+
+    // don't create an enzo.yaml in a repo that has none just to say there's nothing to do
+    if store == TodoStore::Repo && (file.exists() || !project.todos.is_empty()) {
+        config::project::write_todos(&file, &project.todos)?;
+        project.set_seen_todos(project.todos.clone());
+    }
+    Ok(())
+}
+
+/// Three-way merges the todos in a project's `enzo.yaml` with the local ones.
+/// Conflicts go to the side in `prefer`, or to the user if it is `None`.
+pub fn sync_todos(
+    config: &mut Config,
+    src: Option<&str>,
+    prefer: Option<&str>,
+) -> Result<(), EnzoError> {
+    let prefer = match prefer {
+        Some("local") => Some(Side::Local),
+        Some("repo") => Some(Side::Repo),
+        Some(not_supported) => {
+            let msg = format!("Expected `local` or `repo`. Found: {}", not_supported);
+            return Err(EnzoError::new(msg, EnzoErrorKind::FatalError));
+        }
+        None => None,
+    };
+    let path = project_path(config, src)?;
+    let project = get_project_mut(config, &path)?;
+    let conflicts = merge_todos(project, &path.join("enzo.yaml"), prefer)?;
+    utils::success(format!(
+        "synced {} todos of {} ({} conflict{})",
+        project.todos.len(),
+        project.name(),
+        conflicts,
+        if conflicts == 1 { "" } else { "s" }
+    ));
+    Ok(())
+}
+
+/// Merges the todos in `file` into the ones of `project`, and back into
+/// `file` if the project keeps its todos there. Returns how many conflicts
+/// there were.
+fn merge_todos(
+    project: &mut Project,
+    file: &Path,
+    prefer: Option<Side>,
+) -> Result<usize, EnzoError> {
+    let repo = if file.exists() {
+        todos::read_from(&file.to_path_buf())?
+    } else {
+        vec![]
+    };
+
+    let mut conflicts = 0;
+    let merged = todos::sync::merge(project.seen_todos(), &repo, &project.todos, |conflict| {
+        conflicts += 1;
+        match prefer {
+            Some(side) => Ok(side),
+            None if atty::is(atty::Stream::Stdin) => Ok(ask_conflict(conflict)),
+            None => Err(EnzoError::new(
+                "The todos have conflicting changes.\nhint: Pick a side with `--prefer local` or `--prefer repo`",
+                EnzoErrorKind::FatalError,
+            )),
+        }
+    })?;
+
+    if project.todo_store() == TodoStore::Repo {
+        config::project::write_todos(file, &merged)?;
+        project.set_seen_todos(merged.clone());
+    } else {
+        project.set_seen_todos(repo);
+    }
+    project.todos = merged;
+    Ok(conflicts)
+}
+
+fn ask_conflict(conflict: &todos::sync::Conflict) -> Side {
+    let describe = |todo: &Option<todos::todo::Todo>| match todo {
+        Some(todo) => {
+            let mut s = todo.name.clone();
+            s.push_str(&format!(" ({})", todo.status));
+            if let Some(description) = &todo.description {
+                s.push_str(&format!(" - {}", description.replace('\n', " ")));
+            }
+            s
+        }
+        None => String::from("removed"),
+    };
+    eprintln!("{}", ansi_term::Color::Yellow.bold().paint("conflict"));
+    eprintln!("  local: {}", describe(&conflict.local));
+    eprintln!("  repo:  {}", describe(&conflict.repo));
+    let question = Question::new(
+        "keep which version?",
+        Some("local"),
+        None,
+        Some(vec!["local", "repo"]),
+    );
+    let mut answer = AnswerKind::Single(String::new());
+    question.ask(&mut answer);
+    match answer {
+        AnswerKind::Single(s) if s.trim() == "repo" => Side::Repo,
+        _ => Side::Local,
+    }
+}
+
+/// Chooses whether a project's todos are kept in its `enzo.yaml` or only in
+/// the global config.
+pub fn set_todo_store(
+    config: &mut Config,
+    src: Option<&str>,
+    store: &str,
+) -> Result<(), EnzoError> {
+    let store = match store {
+        "repo" => TodoStore::Repo,
+        "private" => TodoStore::Private,
+        not_supported => {
+            let msg = format!("Expected `repo` or `private`. Found: {}", not_supported);
+            return Err(EnzoError::new(msg, EnzoErrorKind::FatalError));
         }
     };
-    let todos = &mut project.todos;
-    todos::start(todos)?;
+    let path = project_path(config, src)?;
+    let project = get_project_mut(config, &path)?;
+    project.set_todo_store(store);
+    if store == TodoStore::Repo {
+        // neither the todos in the repo nor the local ones are lost
+        merge_todos(project, &path.join("enzo.yaml"), None)?;
+    }
+    utils::success(format!(
+        "the todos of {} are now kept in {}",
+        project.name(),
+        match store {
+            TodoStore::Repo => "its enzo.yaml",
+            TodoStore::Private => "your config",
+        }
+    ));
     Ok(())
 }
 
+fn project_path(config: &mut Config, src: Option<&str>) -> Result<PathBuf, EnzoError> {
+    if let Some(src) = src {
+        let (_, dst) = resolve_dst(config, src)?;
+        utils::info(format!("src = {:?}", dst));
+        Ok(dst)
+    } else {
+        Ok(std::env::current_dir()?)
+    }
+}
+
+fn get_project_mut<'a>(
+    config: &'a mut Config,
+    path: &PathBuf,
+) -> Result<&'a mut Project, EnzoError> {
+    match config.get_project_mut(path) {
+        Some(project) => Ok(project),
+        None => Err(EnzoError::new(
+            format!("The project at {:?} does not exist", path),
+            EnzoErrorKind::IOError,
+        )),
+    }
+}
+
 pub fn configure(config: &mut Config, src: Option<&str>) -> Result<(), EnzoError> {
     let mut path = if let Some(src) = src {
         let (_, dst) = resolve_dst(config, src)?;
@@ -135,10 +1041,3 @@ pub fn deploy(config: &mut Config, src: Option<&str>) -> Result<(), EnzoError> {
     configure(config, src)?;
     Ok(())
 }
-
-fn get_repo_name<'a>(src: &'a str) -> Option<&'a str> {
-    match src.rfind("/") {
-        Some(i) => Some(&src[i + 1..]),
-        None => None,
-    }
-}
@@ -1,29 +1,216 @@
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches};
 use enzo::{
-    config::global::Config,
-    utils,
+    config::file::ConfigFile,
     utils::error::{EnzoError, EnzoErrorKind},
-    workspace,
+    workspace, CloneOptions, NewOptions,
 };
-use std::convert::TryFrom;
-use std::fs::File;
-use std::io::prelude::*;
 
-fn main() -> Result<(), EnzoError> {
-    let mut config = read_config()?;
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), EnzoError> {
+    let matches = app().get_matches();
+
+    if let Some(init_matches) = matches.subcommand_matches("shell-init") {
+        return enzo::shell_init(&mut app(), init_matches.value_of("shell").unwrap());
+    }
+
+    // the workspace lookup and completions run inside the shell, where nobody can answer
+    let interactive =
+        matches.value_of("workspace").is_none() && matches.subcommand_name() != Some("complete");
+    let path = ConfigFile::locate(matches.value_of("config"), interactive)?;
+    let mut config_file = ConfigFile::open(path)?;
+    let mut config = config_file.read()?;
+
+    if let Some(name) = matches.value_of("workspace") {
+        let (_, path) = enzo::resolve_dst(&mut config, name)?;
+        println!("{}", path.to_str().unwrap());
+        return Ok(());
+    }
+
+    if let Some(complete_matches) = matches.subcommand_matches("complete") {
+        let word = complete_matches.value_of("word").unwrap_or("");
+        for candidate in enzo::complete(&config, word) {
+            println!("{}", candidate);
+        }
+        return Ok(());
+    }
+
+    let res = match matches.subcommand() {
+        ("add", Some(add_matches)) => match add_matches.value_of("entity") {
+            Some("workspace") => {
+                let (name, data) = workspace::query_workspace()?;
+                enzo::add_workspace(&mut config, name, data)
+            }
+            Some("host") => enzo::add_host(&mut config),
+            Some("todo") => unimplemented!(),
+            Some(not_supported) => {
+                let msg = format!(
+                    "Expected `workspace`, `host` or `todo`. Found: {}",
+                    not_supported
+                );
+                Err(EnzoError::new(msg, EnzoErrorKind::FatalError))
+            }
+            None => Err(EnzoError::new(
+                "Expected `workspace`, `host` or `todo`. Found nothing.",
+                EnzoErrorKind::FatalError,
+            )),
+        },
+        ("rm", Some(rm_matches)) => match rm_matches.value_of("entity") {
+            Some("workspace") => match rm_matches.value_of("name") {
+                Some(name) => enzo::remove_workspace(&mut config, name),
+                None => Err(EnzoError::new(
+                    "Expected the name of the workspace to remove. Found nothing.",
+                    EnzoErrorKind::FatalError,
+                )),
+            },
+            Some("project") => enzo::remove_project(&mut config, rm_matches.value_of("name")),
+            Some(not_supported) => {
+                let msg = format!(
+                    "Expected `workspace` or `project`. Found: {}",
+                    not_supported
+                );
+                Err(EnzoError::new(msg, EnzoErrorKind::FatalError))
+            }
+            None => unreachable!(),
+        },
+        ("rename", Some(rename_matches)) => match rename_matches.value_of("entity") {
+            Some("workspace") => enzo::rename_workspace(
+                &mut config,
+                rename_matches.value_of("from").unwrap(),
+                rename_matches.value_of("to").unwrap(),
+            ),
+            Some(not_supported) => {
+                let msg = format!("Expected `workspace`. Found: {}", not_supported);
+                Err(EnzoError::new(msg, EnzoErrorKind::FatalError))
+            }
+            None => unreachable!(),
+        },
+        ("mv", Some(mv_matches)) => enzo::move_project(
+            &mut config,
+            mv_matches.value_of("project").unwrap(),
+            mv_matches.value_of("dst").unwrap(),
+        ),
+        ("clone", Some(clone_matches)) => {
+            let src = clone_matches.value_of("src").unwrap();
+            let dst = clone_matches.value_of("dst").unwrap();
+            clone_options(clone_matches)
+                .and_then(|options| enzo::clone(&mut config, src, dst, None, &options))
+        }
+        ("new", Some(clone_matches)) => {
+            let src = clone_matches.value_of("src").unwrap();
+            let dst = clone_matches.value_of("dst").unwrap();
+            let new_options = NewOptions {
+                keep_history: clone_matches.is_present("keep-history"),
+                record_template: clone_matches.is_present("record-template"),
+                origin: clone_matches.value_of("origin").map(String::from),
+            };
+            clone_options(clone_matches)
+                .and_then(|options| enzo::new(&mut config, src, dst, &options, &new_options))
+        }
+        ("templates", Some(templates_matches)) => match templates_matches.subcommand() {
+            ("refresh", Some(matches)) => enzo::refresh_templates(&config, matches.value_of("src")),
+            ("prune", Some(matches)) => enzo::prune_templates(&config, matches.value_of("src")),
+            _ => enzo::list_templates(),
+        },
+        ("doctor", Some(doctor_matches)) => enzo::doctor(
+            &mut config,
+            doctor_matches.is_present("fix"),
+            doctor_matches.is_present("interactive"),
+        ),
+        ("list", Some(list_matches)) => enzo::list(&config, list_matches.is_present("json")),
+        ("scan", Some(scan_matches)) => {
+            enzo::scan(&mut config, scan_matches.value_of("workspace").unwrap())
+        }
+        ("status", Some(status_matches)) => enzo::status(
+            &mut config,
+            status_matches.value_of("workspace"),
+            status_matches.is_present("json"),
+        ),
+        ("sync", Some(sync_matches)) => {
+            enzo::sync(&mut config, sync_matches.value_of("workspace").unwrap())
+        }
+        ("todos", todos_matches) => {
+            if let Some(matches) = todos_matches {
+                if let Some(sync_matches) = matches.subcommand_matches("sync") {
+                    enzo::sync_todos(
+                        &mut config,
+                        sync_matches.value_of("src"),
+                        sync_matches.value_of("prefer"),
+                    )
+                } else {
+                    match matches.value_of("store") {
+                        Some(store) => {
+                            enzo::set_todo_store(&mut config, matches.value_of("src"), store)
+                        }
+                        None => enzo::start_task_manager(&mut config, matches.value_of("src")),
+                    }
+                }
+            } else {
+                enzo::start_task_manager(&mut config, None)
+            }
+        }
+        ("configure", _) => enzo::configure(&mut config, None),
+        ("deploy", deploy_matches) => {
+            if let Some(matches) = deploy_matches {
+                enzo::deploy(&mut config, matches.value_of("template"))
+            } else {
+                enzo::deploy(&mut config, None)
+            }
+        }
+        _ => unreachable!(),
+    };
+    // leave the config untouched if the command failed, it may have stopped halfway through
+    res?;
 
-    let matches = App::new("enzo")
+    config_file.write(&config)?;
+    Ok(())
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("enzo")
         .version("0.0.1")
         .about("Workspace and repo management made fun ;)")
         .arg(Arg::with_name("workspace"))
+        .arg(
+            Arg::with_name("config")
+                .help("path to the config file to use instead of the default one")
+                .long("config")
+                .takes_value(true)
+                .global(true),
+        )
         .subcommand(
             App::new("add")
-                .about("add a `workspace` or a `task`")
+                .about("add a `workspace`, a `host` alias or a `task`")
                 .arg(Arg::with_name("entity").required(true)),
         )
+        .subcommand(
+            App::new("rm")
+                .about("remove a `workspace` or a `project` from enzo, leaving the files on disk")
+                .arg(Arg::with_name("entity").required(true))
+                .arg(Arg::with_name("name")),
+        )
+        .subcommand(
+            App::new("rename")
+                .about("rename a `workspace`")
+                .arg(Arg::with_name("entity").required(true))
+                .arg(Arg::with_name("from").required(true))
+                .arg(Arg::with_name("to").required(true)),
+        )
+        .subcommand(
+            App::new("mv")
+                .about("Move a project to another place or workspace")
+                .arg(Arg::with_name("project").required(true))
+                .arg(Arg::with_name("dst").required(true)),
+        )
         .subcommand(
             App::new("clone")
                 .about("Clone a git repo into a workspace")
+                .args(&clone_args())
                 .arg(Arg::with_name("src").required(true))
                 .arg(Arg::with_name("dst").required(true))
                 .arg(
@@ -43,6 +230,24 @@ fn main() -> Result<(), EnzoError> {
         .subcommand(
             App::new("new")
                 .about("Clone a template repo into a workspace")
+                .args(&clone_args())
+                .arg(
+                    Arg::with_name("keep-history")
+                        .help("keep the template's history instead of starting a new one")
+                        .long("keep-history"),
+                )
+                .arg(
+                    Arg::with_name("record-template")
+                        .help("mention the template's url and commit in the initial commit")
+                        .long("record-template")
+                        .conflicts_with("keep-history"),
+                )
+                .arg(
+                    Arg::with_name("origin")
+                        .help("remote to set as `origin` of the new project")
+                        .long("origin")
+                        .takes_value(true),
+                )
                 .arg(Arg::with_name("src").required(true))
                 .arg(Arg::with_name("dst").required(true))
                 .arg(
@@ -59,10 +264,89 @@ fn main() -> Result<(), EnzoError> {
                         .takes_value(true),
                 ),
         )
+        .subcommand(
+            App::new("templates")
+                .about("Manage the local cache of templates used by `enzo new`")
+                .subcommand(App::new("list").about("List cached templates"))
+                .subcommand(
+                    App::new("refresh")
+                        .about("Fetch the latest version of one or all cached templates")
+                        .arg(Arg::with_name("src")),
+                )
+                .subcommand(
+                    App::new("prune")
+                        .about("Remove one or all cached templates")
+                        .arg(Arg::with_name("src")),
+                ),
+        )
+        .subcommand(
+            App::new("doctor")
+                .about("Check your workspaces and projects for problems")
+                .arg(
+                    Arg::with_name("fix")
+                        .help("fix every problem that can be fixed")
+                        .long("fix"),
+                )
+                .arg(
+                    Arg::with_name("interactive")
+                        .help("ask before fixing each problem")
+                        .short("i")
+                        .long("interactive")
+                        .conflicts_with("fix"),
+                ),
+        )
+        .subcommand(
+            App::new("list")
+                .about("List your workspaces and the projects in them")
+                .arg(
+                    Arg::with_name("json")
+                        .help("print the workspaces as json")
+                        .long("json"),
+                ),
+        )
+        .subcommand(
+            App::new("scan")
+                .about("Find the repos in a workspace and register them as projects")
+                .arg(Arg::with_name("workspace").required(true)),
+        )
+        .subcommand(
+            App::new("status")
+                .about("Show the git status of every project in a workspace")
+                .arg(Arg::with_name("workspace"))
+                .arg(
+                    Arg::with_name("json")
+                        .help("print the status as json")
+                        .long("json"),
+                ),
+        )
+        .subcommand(
+            App::new("sync")
+                .about("Fetch every project in a workspace and fast-forward clean branches")
+                .arg(Arg::with_name("workspace").required(true)),
+        )
         .subcommand(
             App::new("todos")
                 .about("Manage your todos")
-                .arg(Arg::with_name("src")),
+                .arg(Arg::with_name("src"))
+                .arg(
+                    Arg::with_name("store")
+                        .help("keep the project's todos in its enzo.yaml or only in your config")
+                        .long("store")
+                        .takes_value(true)
+                        .possible_values(&["repo", "private"]),
+                )
+                .subcommand(
+                    App::new("sync")
+                        .about("Merge the todos in the project's enzo.yaml with your own")
+                        .arg(Arg::with_name("src"))
+                        .arg(
+                            Arg::with_name("prefer")
+                                .help("side to keep when both changed a todo, instead of asking")
+                                .long("prefer")
+                                .takes_value(true)
+                                .possible_values(&["local", "repo"]),
+                        ),
+                ),
         )
         .subcommand(
             App::new("configure")
@@ -77,81 +361,66 @@ fn main() -> Result<(), EnzoError> {
                     .takes_value(true),
             ),
         )
-        .get_matches();
+        .subcommand(
+            App::new("shell-init")
+                .about("Print a shell function that cds into workspaces, and completions")
+                .arg(
+                    Arg::with_name("shell")
+                        .required(true)
+                        .possible_values(&["bash", "zsh", "fish"]),
+                ),
+        )
+        .subcommand(
+            App::new("complete")
+                .setting(AppSettings::Hidden)
+                .arg(Arg::with_name("word")),
+        )
+}
 
-    if let Some(name) = matches.value_of("workspace") {
-        let (_, path) = enzo::resolve_dst(&mut config, name)?;
-        println!("{}", path.to_str().unwrap());
-        return Ok(());
-    }
+fn clone_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("branch")
+            .help("branch to check out instead of the remote's HEAD")
+            .short("b")
+            .long("branch")
+            .takes_value(true)
+            .conflicts_with_all(&["tag", "rev"]),
+        Arg::with_name("tag")
+            .help("tag to check out")
+            .long("tag")
+            .takes_value(true)
+            .conflicts_with("rev"),
+        Arg::with_name("rev")
+            .help("commit to check out")
+            .long("rev")
+            .takes_value(true),
+        Arg::with_name("depth")
+            .help("create a shallow clone with this many commits of history")
+            .long("depth")
+            .takes_value(true),
+        Arg::with_name("no-submodules")
+            .help("do not initialize submodules after cloning")
+            .long("no-submodules"),
+    ]
+}
 
-    let res = match matches.subcommand() {
-        ("add", Some(add_matches)) => match add_matches.value_of("entity") {
-            Some("workspace") => {
-                let (name, data) = workspace::query_workspace()?;
-                config.add_workspace(name, data.path);
-                Ok(())
-            }
-            Some("todo") => unimplemented!(),
-            Some(not_supported) => {
-                let msg = format!("Expected `workspace` or `todo`. Found: {}", not_supported);
-                Err(EnzoError::new(msg, EnzoErrorKind::FatalError))
+fn clone_options(matches: &ArgMatches) -> Result<CloneOptions, EnzoError> {
+    let depth = match matches.value_of("depth") {
+        Some(depth) => match depth.parse::<i32>() {
+            Ok(depth) if depth > 0 => Some(depth),
+            _ => {
+                let msg = format!("Expected `depth` to be a positive number. Found: {}", depth);
+                return Err(EnzoError::new(msg, EnzoErrorKind::FatalError));
             }
-            None => Err(EnzoError::new(
-                "Expected `workspace` or `todo`. Found nothing.",
-                EnzoErrorKind::FatalError,
-            )),
         },
-        ("clone", Some(clone_matches)) => {
-            let src = clone_matches.value_of("src").unwrap();
-            let dst = clone_matches.value_of("dst").unwrap();
-            enzo::clone(&mut config, src, dst, None)
-        }
-        ("new", Some(clone_matches)) => {
-            let src = clone_matches.value_of("src").unwrap();
-            let dst = clone_matches.value_of("dst").unwrap();
-            enzo::new(&mut config, src, dst)
-        }
-        ("todos", todos_matches) => {
-            if let Some(matches) = todos_matches {
-                enzo::start_task_manager(&mut config, matches.value_of("src"))
-            } else {
-                enzo::start_task_manager(&mut config, None)
-            }
-        }
-        ("configure", _) => enzo::configure(&mut config, None),
-        ("deploy", deploy_matches) => {
-            if let Some(matches) = deploy_matches {
-                enzo::deploy(&mut config, matches.value_of("template"))
-            } else {
-                enzo::deploy(&mut config, None)
-            }
-        }
-        _ => unreachable!(),
+        None => None,
     };
-    if let Err(e) = res {
-        eprintln!("{}", e);
-    }
-
-    write_config(config)?;
-    Ok(())
-}
-
-fn read_config() -> Result<Config, EnzoError> {
-    let mut path = utils::get_home_dir()?;
-    path.push(".enzo.config.yaml");
-    if !path.exists() {
-        // warn that the config file didn't exist
-        let mut file = File::create(&path)?;
-        file.write_all(Config::default().to_string()?.as_bytes())?;
-    }
-    Config::try_from(path)
-}
-
-fn write_config(config: Config) -> Result<(), EnzoError> {
-    let mut path = utils::get_home_dir()?;
-    path.push(".enzo.config.yaml");
-    let mut file = File::create(&path)?;
-    file.write_all(config.to_string()?.as_bytes())?;
-    Ok(())
+    Ok(CloneOptions {
+        branch: matches.value_of("branch").map(String::from),
+        tag: matches.value_of("tag").map(String::from),
+        rev: matches.value_of("rev").map(String::from),
+        depth,
+        submodules: !matches.is_present("no-submodules"),
+        ..Default::default()
+    })
 }
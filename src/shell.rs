@@ -0,0 +1,139 @@
+use crate::config::global::Config;
+use clap::{App, Shell};
+use std::{fs, str::FromStr};
+
+/// A function that `cd`s into the path `enzo <workspace>` prints and passes
+/// everything else through. A single argument is treated as a workspace
+/// unless `enzo help` knows it as a subcommand.
+const BASH_FUNCTION: &str = r#"
+enzo() {
+    if [ "$#" -eq 1 ] && [ "${1#-}" = "$1" ] && ! command enzo help "$1" >/dev/null 2>&1; then
+        local dir
+        dir="$(command enzo "$1")" || return
+        cd -- "$dir"
+    else
+        command enzo "$@"
+    fi
+}
+"#;
+
+const BASH_COMPLETION: &str = r#"
+_enzo_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "$cur" != -* ]] && { [ "$COMP_CWORD" -eq 1 ] || [[ "$COMP_CWORD" -eq 2 && "$prev" =~ ^(scan|status|sync)$ ]]; }; then
+        local IFS=$'\n'
+        local workspaces=($(command enzo complete "$cur" 2>/dev/null))
+        if [ "${#workspaces[@]}" -gt 0 ]; then
+            _enzo
+            COMPREPLY+=("${workspaces[@]}")
+            compopt -o nospace
+            return
+        fi
+    fi
+    _enzo
+}
+complete -F _enzo_dynamic -o bashdefault -o default enzo
+"#;
+
+const ZSH_COMPLETION: &str = r#"
+_enzo_dynamic() {
+    if (( CURRENT == 2 )) || [[ CURRENT -eq 3 && "${words[2]}" =~ ^(scan|status|sync)$ ]]; then
+        local -a workspaces
+        workspaces=("${(@f)$(command enzo complete "$PREFIX" 2>/dev/null)}")
+        compadd -S '' -- ${workspaces:#}
+    fi
+    _enzo "$@"
+}
+compdef _enzo_dynamic enzo
+"#;
+
+const FISH_FUNCTION: &str = r#"
+function enzo
+    if test (count $argv) -eq 1; and not string match -q -- '-*' $argv[1]; and not command enzo help $argv[1] >/dev/null 2>&1
+        set -l dir (command enzo $argv[1]); or return
+        cd -- "$dir"
+    else
+        command enzo $argv
+    end
+end
+"#;
+
+const FISH_COMPLETION: &str = r#"
+complete -c enzo -n "__fish_use_subcommand" -f -a "(command enzo complete (commandline -ct) 2>/dev/null)"
+complete -c enzo -n "__fish_seen_subcommand_from scan status sync" -f -a "(command enzo complete (commandline -ct) 2>/dev/null)"
+"#;
+
+/// The script to `eval` in `shell` to get the `enzo` function and completions.
+pub fn init(app: &mut App, shell: &str) -> Option<String> {
+    let mut completions = vec![];
+    app.gen_completions_to("enzo", Shell::from_str(shell).ok()?, &mut completions);
+    let completions = String::from_utf8_lossy(&completions);
+
+    let script = match shell {
+        // the workspace is completed by `_enzo_dynamic` instead of as a literal
+        "bash" => format!(
+            "{}{}{}",
+            BASH_FUNCTION,
+            completions.replace(" <workspace> ", " "),
+            BASH_COMPLETION
+        ),
+        // the generated script calls `_enzo` as if it was autoloaded from fpath,
+        // and puts the help in double quotes where backticks would be run
+        "zsh" => format!(
+            "{}{}{}",
+            BASH_FUNCTION,
+            completions.replace("_enzo \"$@\"", "").replace('`', "'"),
+            ZSH_COMPLETION
+        ),
+        "fish" => format!("{}{}{}", FISH_FUNCTION, completions, FISH_COMPLETION),
+        _ => return None,
+    };
+    Some(script)
+}
+
+/// Completes `word` as a workspace name or as a path inside a workspace, so
+/// that `hackgt.websites/hor` completes to `hackgt.websites/horizons/`.
+pub fn complete(config: &Config, word: &str) -> Vec<String> {
+    let mut candidates = config
+        .workspaces()
+        .map(|(name, _)| name.0.clone())
+        .filter(|name| name.starts_with(word))
+        .collect::<Vec<_>>();
+
+    if let Some(i) = word.rfind('/') {
+        let (head, tail) = (&word[..i], &word[i + 1..]);
+        if let Some((_, dir)) = config.resolve_path(head) {
+            if let Ok(entries) = fs::read_dir(dir) {
+                for entry in entries.filter_map(|entry| entry.ok()) {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    if entry.path().is_dir() && !name.starts_with('.') && name.starts_with(tail) {
+                        candidates.push(format!("{}/{}/", head, name));
+                    }
+                }
+            }
+        }
+    }
+
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn complete_workspace_names() {
+        let mut config = Config::default();
+        config.add_workspace("hackgt", PathBuf::from("/enzo/shell/hackgt"));
+        config.add_workspace("hackgt.websites", PathBuf::from("/enzo/shell/websites"));
+        config.add_workspace("college", PathBuf::from("/enzo/shell/college"));
+
+        assert_eq!(complete(&config, "hack"), vec!["hackgt", "hackgt.websites"]);
+        assert_eq!(complete(&config, "college/"), Vec::<String>::new());
+        assert_eq!(complete(&config, "").len(), 3);
+    }
+}
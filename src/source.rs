@@ -0,0 +1,183 @@
+use std::path::Path;
+
+/// A git remote that a repo can be cloned from, broken down into the pieces
+/// git's credential helpers care about.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Source {
+    pub url: String,
+    pub protocol: String,
+    pub host: Option<String>,
+    pub path: String,
+}
+
+impl Source {
+    /// Parses a full url (`https://host/org/repo.git`), an scp-style address
+    /// (`git@host:org/repo.git`) or a local path.
+    pub fn parse<T: Into<String>>(url: T) -> Self {
+        let url = url.into();
+        if let Some(i) = url.find("://") {
+            let protocol = url[..i].to_string();
+            let rest = &url[i + 3..];
+            if protocol == "file" {
+                return Source {
+                    protocol,
+                    host: None,
+                    path: rest.to_string(),
+                    url,
+                };
+            }
+            let (authority, path) = match rest.find('/') {
+                Some(j) => (&rest[..j], &rest[j + 1..]),
+                None => (rest, ""),
+            };
+            let host = strip_user(authority);
+            return Source {
+                protocol,
+                host: Some(host.to_string()),
+                path: path.to_string(),
+                url,
+            };
+        }
+        if is_scp_like(&url) {
+            let i = url.find(':').unwrap();
+            let host = strip_user(&url[..i]).to_string();
+            let path = url[i + 1..].to_string();
+            return Source {
+                protocol: "ssh".into(),
+                host: Some(host),
+                path,
+                url,
+            };
+        }
+        Source {
+            protocol: "file".into(),
+            host: None,
+            path: url.clone(),
+            url,
+        }
+    }
+
+    /// The name a clone of this source should get by default, which is the
+    /// last component of its path without the `.git` suffix.
+    pub fn repo_name(&self) -> Option<&str> {
+        let path = self.path.trim_end_matches('/');
+        let name = match path.rfind('/') {
+            Some(i) => &path[i + 1..],
+            None => path,
+        };
+        let name = name.trim_end_matches(".git");
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+}
+
+/// Whether `src` is already something git understands as a remote, as opposed
+/// to the `<username>/<repo>` or `<alias>:<path>` shorthands enzo expands.
+pub fn is_explicit(src: &str) -> bool {
+    src.contains("://")
+        || src.starts_with('/')
+        || src.starts_with("./")
+        || src.starts_with("../")
+        || src.starts_with('~')
+        || (is_scp_like(src) && src[..src.find(':').unwrap()].contains('@'))
+        || Path::new(src).exists()
+}
+
+/// Splits an `<alias>:<path>` shorthand into its parts.
+pub fn split_alias(src: &str) -> Option<(&str, &str)> {
+    if is_scp_like(src) {
+        let i = src.find(':').unwrap();
+        Some((&src[..i], &src[i + 1..]))
+    } else {
+        None
+    }
+}
+
+/// Joins a host alias' base url with a repo path, e.g. `https://gitlab.com`
+/// and `team/repo` or `git@gitlab.com:` and `team/repo`.
+pub fn expand(base: &str, path: &str) -> String {
+    let path = path.trim_start_matches('/').trim_end_matches('/');
+    let suffix = if path.ends_with(".git") { "" } else { ".git" };
+    if base.ends_with(':') {
+        format!("{}{}{}", base, path, suffix)
+    } else {
+        format!("{}/{}{}", base.trim_end_matches('/'), path, suffix)
+    }
+}
+
+fn is_scp_like(src: &str) -> bool {
+    match (src.find(':'), src.find('/')) {
+        // a single letter before the colon is a windows drive, not a host
+        (Some(i), Some(j)) => i > 1 && i < j,
+        (Some(i), None) => i > 1,
+        _ => false,
+    }
+}
+
+fn strip_user(authority: &str) -> &str {
+    match authority.rfind('@') {
+        Some(i) => &authority[i + 1..],
+        None => authority,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_urls() {
+        let src = Source::parse("https://gitlab.com/team/repo.git");
+        assert_eq!(src.protocol, "https");
+        assert_eq!(src.host, Some("gitlab.com".into()));
+        assert_eq!(src.path, "team/repo.git");
+        assert_eq!(src.repo_name(), Some("repo"));
+
+        let src = Source::parse("ssh://git@gitea.example.com:2222/org/repo");
+        assert_eq!(src.protocol, "ssh");
+        assert_eq!(src.host, Some("gitea.example.com:2222".into()));
+        assert_eq!(src.repo_name(), Some("repo"));
+
+        let src = Source::parse("file:///srv/git/repo.git/");
+        assert_eq!(src.protocol, "file");
+        assert_eq!(src.host, None);
+        assert_eq!(src.repo_name(), Some("repo"));
+    }
+
+    #[test]
+    fn parse_scp_and_paths() {
+        let src = Source::parse("git@bitbucket.org:team/repo.git");
+        assert_eq!(src.protocol, "ssh");
+        assert_eq!(src.host, Some("bitbucket.org".into()));
+        assert_eq!(src.path, "team/repo.git");
+        assert_eq!(src.repo_name(), Some("repo"));
+
+        let src = Source::parse("../templates/rust");
+        assert_eq!(src.protocol, "file");
+        assert_eq!(src.host, None);
+        assert_eq!(src.repo_name(), Some("rust"));
+    }
+
+    #[test]
+    fn shorthands() {
+        assert!(is_explicit("git@github.com:HackGT/enzo"));
+        assert!(is_explicit("https://github.com/HackGT/enzo"));
+        assert!(!is_explicit("HackGT/enzo"));
+        assert!(!is_explicit("gl:team/repo"));
+
+        assert_eq!(split_alias("gl:team/repo"), Some(("gl", "team/repo")));
+        assert_eq!(split_alias("HackGT/enzo"), None);
+
+        assert_eq!(
+            expand("https://gitlab.com/", "team/repo"),
+            "https://gitlab.com/team/repo.git"
+        );
+        assert_eq!(
+            expand("git@gitlab.com:", "team/repo.git"),
+            "git@gitlab.com:team/repo.git"
+        );
+    }
+}
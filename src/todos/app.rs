@@ -1,23 +1,157 @@
-use crate::todos::Todo;
+use crate::todos::{
+    input::Editor,
+    todo::{self, Status, Todo},
+};
+use std::{collections::HashSet, fmt};
 use tui::widgets::ListState;
 
+pub enum Mode {
+    Normal,
+    Editing(Box<Editor>),
+    ConfirmDelete(Vec<usize>),
+}
+
+/// The order the todos are shown in. `Manual` is the order they are saved in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Sort {
+    Manual,
+    Priority,
+    Due,
+    Status,
+}
+
+impl Sort {
+    fn next(self) -> Self {
+        match self {
+            Sort::Manual => Sort::Priority,
+            Sort::Priority => Sort::Due,
+            Sort::Due => Sort::Status,
+            Sort::Status => Sort::Manual,
+        }
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Sort::Manual => "manual",
+            Sort::Priority => "priority",
+            Sort::Due => "due date",
+            Sort::Status => "status",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StatusFilter {
+    All,
+    Open,
+    Only(Status),
+}
+
+impl StatusFilter {
+    fn next(self) -> Self {
+        match self {
+            StatusFilter::All => StatusFilter::Open,
+            StatusFilter::Open => StatusFilter::Only(Status::ALL[0]),
+            StatusFilter::Only(Status::Done) => StatusFilter::All,
+            StatusFilter::Only(status) => StatusFilter::Only(status.next()),
+        }
+    }
+
+    fn matches(self, todo: &Todo) -> bool {
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Open => !todo.is_complete(),
+            StatusFilter::Only(status) => todo.status == status,
+        }
+    }
+}
+
+/// A change to the todo list that can be undone and redone. Changes to
+/// subtasks are recorded as an `Edit` of the top-level todo they belong to,
+/// along with the path of the todo to select.
+#[derive(Clone, Debug)]
+enum Change {
+    Add(usize, Box<Todo>),
+    Edit(Vec<usize>, Box<Todo>, Box<Todo>),
+    Delete(usize, Box<Todo>),
+    Move(usize, usize),
+}
+
+/// A row of the list.
+pub struct Entry<'b> {
+    pub todo: &'b Todo,
+    /// How many ancestors the todo has.
+    pub depth: usize,
+    pub collapsed: bool,
+}
+
+/// The todos form a tree through their `children`. A todo in it is found by
+/// a path: its index in `todos`, then its index in the children of that
+/// todo, and so on.
 pub struct App<'a> {
     pub todos: &'a mut Vec<Todo>,
+    /// The selected row of `view`.
     pub state: ListState,
+    pub mode: Mode,
+    pub sort: Sort,
+    pub status_filter: StatusFilter,
+    pub tag_filter: Option<String>,
+    /// The paths of the todos that are shown, in the order they are shown.
+    view: Vec<Vec<usize>>,
+    /// The ids of the todos whose children are hidden.
+    collapsed: HashSet<String>,
+    /// The ids given to todos that had none, taken back when the app is
+    /// dropped so that syncing can still match those todos by name.
+    session_ids: HashSet<String>,
+    undo: Vec<Change>,
+    redo: Vec<Change>,
 }
 
 impl<'a> App<'a> {
     pub fn with_todos(todos: &'a mut Vec<Todo>) -> Self {
-        App {
+        // collapsing a todo needs a way to tell it apart from the others
+        let mut session_ids = HashSet::new();
+        assign_ids(todos, &mut session_ids);
+        let mut app = App {
             todos,
             state: ListState::default(),
-        }
+            mode: Mode::Normal,
+            sort: Sort::Manual,
+            status_filter: StatusFilter::All,
+            tag_filter: None,
+            view: vec![],
+            collapsed: HashSet::new(),
+            session_ids,
+            undo: vec![],
+            redo: vec![],
+        };
+        app.refresh(Some(vec![0]));
+        app
+    }
+
+    /// The todos that are shown, in the order they are shown.
+    pub fn visible(&self) -> impl Iterator<Item = Entry<'_>> {
+        self.view.iter().filter_map(move |path| {
+            let todo = get(self.todos, path)?;
+            Some(Entry {
+                todo,
+                depth: path.len() - 1,
+                collapsed: self.collapsed.contains(&todo.id),
+            })
+        })
     }
 
     pub fn next(&mut self) {
+        if self.view.is_empty() {
+            self.state.select(None);
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
-                if i == self.todos.len() - 1 {
+                if i >= self.view.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -29,10 +163,14 @@ impl<'a> App<'a> {
     }
 
     pub fn previous(&mut self) {
+        if self.view.is_empty() {
+            self.state.select(None);
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
-                if i == 0 {
-                    self.todos.len() - 1
+                if i == 0 || i > self.view.len() - 1 {
+                    self.view.len() - 1
                 } else {
                     i - 1
                 }
@@ -42,13 +180,563 @@ impl<'a> App<'a> {
         self.state.select(Some(i));
     }
 
-    pub fn current(&self) -> &Todo {
-        let i = self.state.selected().unwrap_or(0);
-        &self.todos.get(i).unwrap()
+    pub fn current(&self) -> Option<&Todo> {
+        get(self.todos, &self.selected()?)
+    }
+
+    /// Shows the subtasks of the selected todo.
+    pub fn expand(&mut self) {
+        if let Some(path) = self.selected() {
+            if let Some(todo) = get(self.todos, &path) {
+                if self.collapsed.remove(&todo.id) {
+                    self.refresh(Some(path));
+                }
+            }
+        }
+    }
+
+    /// Hides the subtasks of the selected todo, or selects its parent when
+    /// there are none to hide.
+    pub fn collapse(&mut self) {
+        if let Some(mut path) = self.selected() {
+            match get(self.todos, &path) {
+                Some(todo) if !todo.children.is_empty() && !self.collapsed.contains(&todo.id) => {
+                    self.collapsed.insert(todo.id.clone());
+                }
+                _ if path.len() > 1 => {
+                    path.pop();
+                }
+                _ => return,
+            }
+            self.refresh(Some(path));
+        }
+    }
+
+    pub fn start_adding(&mut self) {
+        self.mode = Mode::Editing(Box::new(Editor::new()));
+    }
+
+    /// Starts adding a subtask to the selected todo.
+    pub fn start_adding_subtask(&mut self) {
+        if let Some(path) = self.selected() {
+            self.mode = Mode::Editing(Box::new(Editor::subtask_of(path)));
+        }
+    }
+
+    pub fn start_editing(&mut self) {
+        if let Some(path) = self.selected() {
+            if let Some(todo) = get(self.todos, &path) {
+                self.mode = Mode::Editing(Box::new(Editor::for_todo(path, todo)));
+            }
+        }
+    }
+
+    pub fn cancel_editing(&mut self) {
+        self.mode = Mode::Normal;
+    }
+
+    /// Saves the todo being edited, unless what was entered is invalid.
+    pub fn submit(&mut self) {
+        let (editing, parent, result) = match &self.mode {
+            Mode::Editing(editor) => {
+                let editing = editor
+                    .editing
+                    .clone()
+                    .filter(|path| get(self.todos, path).is_some());
+                let mut todo = match &editing {
+                    Some(path) => get(self.todos, path).unwrap().clone(),
+                    None => Todo::new(String::new(), None),
+                };
+                let parent = editor
+                    .parent
+                    .clone()
+                    .filter(|path| get(self.todos, path).is_some());
+                (editing, parent, editor.apply_to(&mut todo).map(|_| todo))
+            }
+            _ => return,
+        };
+        let todo = match (result, &mut self.mode) {
+            (Ok(todo), _) => todo,
+            (Err(e), Mode::Editing(editor)) => {
+                editor.error = Some(e);
+                return;
+            }
+            _ => return,
+        };
+        match (editing, parent) {
+            (Some(path), _) => self.edit_at(&path, |t| {
+                *t = todo;
+                None
+            }),
+            (None, Some(parent)) => self.edit_at(&parent, |t| {
+                t.children.push(todo);
+                Some(t.children.len() - 1)
+            }),
+            (None, None) => self.record(Change::Add(self.todos.len(), Box::new(todo))),
+        }
+        self.mode = Mode::Normal;
+    }
+
+    pub fn toggle(&mut self) {
+        self.edit_current(|todo| {
+            if todo.is_complete() {
+                todo.mark_incomplete();
+            } else {
+                todo.mark_complete();
+            }
+        });
+    }
+
+    pub fn cycle_status(&mut self) {
+        self.edit_current(|todo| todo.set_status(todo.status.next()));
+    }
+
+    pub fn start_deleting(&mut self) {
+        if let Some(path) = self.selected() {
+            self.mode = Mode::ConfirmDelete(path);
+        }
+    }
+
+    /// Deletes the todo waiting for confirmation, along with its subtasks.
+    pub fn confirm_delete(&mut self) {
+        if let Mode::ConfirmDelete(path) = std::mem::replace(&mut self.mode, Mode::Normal) {
+            match path.split_last() {
+                Some((i, [])) => {
+                    if let Some(todo) = self.todos.get(*i) {
+                        self.record(Change::Delete(*i, Box::new(todo.clone())));
+                    }
+                }
+                Some((i, parent)) if get(self.todos, &path).is_some() => {
+                    let i = *i;
+                    self.edit_at(parent, |t| {
+                        t.children.remove(i);
+                        Some(i)
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Swaps the selected todo with the sibling shown above it. Only the
+    /// manual order can be changed.
+    pub fn move_up(&mut self) {
+        self.move_by(false);
+    }
+
+    /// Swaps the selected todo with the sibling shown below it.
+    pub fn move_down(&mut self) {
+        self.move_by(true);
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.refresh(self.selected());
+    }
+
+    pub fn cycle_status_filter(&mut self) {
+        self.status_filter = self.status_filter.next();
+        self.refresh(self.selected());
+    }
+
+    /// Filters by the next tag in alphabetical order, or stops filtering
+    /// after the last one.
+    pub fn cycle_tag_filter(&mut self) {
+        let mut tags = vec![];
+        collect_tags(self.todos, &mut tags);
+        tags.sort();
+        tags.dedup();
+        self.tag_filter = match &self.tag_filter {
+            Some(current) => tags.into_iter().find(|tag| *tag > current).cloned(),
+            None => tags.first().map(|tag| (*tag).clone()),
+        };
+        self.refresh(self.selected());
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(change) = self.undo.pop() {
+            self.revert(&change);
+            self.redo.push(change);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(change) = self.redo.pop() {
+            self.apply(&change);
+            self.undo.push(change);
+        }
+    }
+
+    /// The path of the selected todo.
+    fn selected(&self) -> Option<Vec<usize>> {
+        self.view.get(self.state.selected()?).cloned()
+    }
+
+    fn edit_current<F: FnOnce(&mut Todo)>(&mut self, f: F) {
+        if let Some(path) = self.selected() {
+            self.edit_at(&path, |todo| {
+                f(todo);
+                None
+            });
+        }
+    }
+
+    /// Records a change to the todo at `path`. When `f` changes one of its
+    /// children, it returns the index of that child so that the change is
+    /// rolled up into the todos above it.
+    fn edit_at<F: FnOnce(&mut Todo) -> Option<usize>>(&mut self, path: &[usize], f: F) {
+        let before = match get(self.todos, &path[..1]) {
+            Some(todo) => todo.clone(),
+            None => return,
+        };
+        let mut after = before.clone();
+        let child = match after.get_mut(&path[1..]) {
+            Some(todo) => f(todo),
+            None => return,
+        };
+        let mut changed = path.to_vec();
+        changed.extend(child);
+        for depth in (1..changed.len()).rev() {
+            if let Some(ancestor) = after.get_mut(&changed[1..depth]) {
+                ancestor.update_from_children();
+            }
+        }
+        // a deleted subtask leaves its parent selected
+        let focus = match after.get(&changed[1..]) {
+            Some(_) => changed,
+            None => path.to_vec(),
+        };
+        self.record(Change::Edit(focus, Box::new(before), Box::new(after)));
+    }
+
+    fn move_by(&mut self, down: bool) {
+        let path = match self.selected() {
+            Some(path) if self.sort == Sort::Manual => path,
+            _ => return,
+        };
+        let (parent, siblings) = {
+            let parent = &path[..path.len() - 1];
+            let siblings = self
+                .view
+                .iter()
+                .filter(|p| p.len() == path.len() && p.starts_with(parent))
+                .map(|p| p[p.len() - 1])
+                .collect::<Vec<_>>();
+            (parent.to_vec(), siblings)
+        };
+        let i = path[path.len() - 1];
+        let row = siblings.iter().position(|s| *s == i).unwrap_or_default();
+        let other = match (down, row) {
+            (true, _) => siblings.get(row + 1),
+            (false, 0) => None,
+            (false, _) => siblings.get(row - 1),
+        };
+        match (other.copied(), parent.is_empty()) {
+            (Some(other), true) => self.record(Change::Move(i, other)),
+            (Some(other), false) => self.edit_at(&parent, |t| {
+                t.children.swap(i, other);
+                Some(other)
+            }),
+            (None, _) => {}
+        }
+    }
+
+    fn record(&mut self, change: Change) {
+        self.apply(&change);
+        self.undo.push(change);
+        self.redo.clear();
+    }
+
+    fn apply(&mut self, change: &Change) {
+        match change {
+            Change::Add(i, todo) => {
+                self.todos.insert(*i, (**todo).clone());
+                self.refresh(Some(vec![*i]));
+            }
+            Change::Edit(focus, _, after) => {
+                self.todos[focus[0]] = (**after).clone();
+                self.refresh(Some(focus.clone()));
+            }
+            Change::Delete(i, _) => {
+                self.todos.remove(*i);
+                self.refresh(None);
+            }
+            Change::Move(from, to) => {
+                self.todos.swap(*from, *to);
+                self.refresh(Some(vec![*to]));
+            }
+        }
+    }
+
+    fn revert(&mut self, change: &Change) {
+        match change {
+            Change::Add(i, _) => {
+                self.todos.remove(*i);
+                self.refresh(None);
+            }
+            Change::Edit(focus, before, _) => {
+                self.todos[focus[0]] = (**before).clone();
+                self.refresh(Some(focus.clone()));
+            }
+            Change::Delete(i, todo) => {
+                self.todos.insert(*i, (**todo).clone());
+                self.refresh(Some(vec![*i]));
+            }
+            Change::Move(from, to) => {
+                self.todos.swap(*from, *to);
+                self.refresh(Some(vec![*from]));
+            }
+        }
+    }
+
+    /// Rebuilds `view` and selects the todo at `focus` if it is shown.
+    /// Otherwise the selected row stays where it was.
+    fn refresh(&mut self, focus: Option<Vec<usize>>) {
+        let mut view = vec![];
+        self.add_rows(self.todos, &mut vec![], &mut view);
+        self.view = view;
+
+        let row = focus
+            .and_then(|focus| self.view.iter().position(|path| *path == focus))
+            .or_else(|| self.state.selected());
+        if self.view.is_empty() {
+            self.state.select(None);
+        } else {
+            self.state
+                .select(Some(row.unwrap_or(0).min(self.view.len() - 1)));
+        }
+    }
+
+    /// Adds the rows for `todos`, the children of the todo at `parent`, and
+    /// for their children unless they are collapsed. Siblings are sorted
+    /// among themselves.
+    fn add_rows(&self, todos: &[Todo], parent: &mut Vec<usize>, view: &mut Vec<Vec<usize>>) {
+        let mut order = (0..todos.len())
+            .filter(|i| self.shows(&todos[*i]))
+            .collect::<Vec<_>>();
+        // sorting is stable, so ties stay in the manual order
+        match self.sort {
+            Sort::Manual => {}
+            Sort::Priority => order.sort_by_key(|i| std::cmp::Reverse(todos[*i].priority)),
+            Sort::Due => order.sort_by_key(|i| (todos[*i].due.is_none(), todos[*i].due)),
+            Sort::Status => order.sort_by_key(|i| match todos[*i].status {
+                Status::InProgress => 0,
+                Status::Todo => 1,
+                Status::Blocked => 2,
+                Status::Done => 3,
+            }),
+        }
+        for i in order {
+            parent.push(i);
+            view.push(parent.clone());
+            if !self.collapsed.contains(&todos[i].id) {
+                self.add_rows(&todos[i].children, parent, view);
+            }
+            parent.pop();
+        }
+    }
+
+    /// Whether a todo passes the filters, or has a subtask that does.
+    fn shows(&self, todo: &Todo) -> bool {
+        let matches = self.status_filter.matches(todo)
+            && match &self.tag_filter {
+                Some(tag) => todo.tags.contains(tag),
+                None => true,
+            };
+        matches || todo.children.iter().any(|child| self.shows(child))
+    }
+}
+
+fn get<'b>(todos: &'b [Todo], path: &[usize]) -> Option<&'b Todo> {
+    let (i, rest) = path.split_first()?;
+    todos.get(*i)?.get(rest)
+}
+
+impl Drop for App<'_> {
+    fn drop(&mut self) {
+        remove_ids(self.todos, &self.session_ids);
+    }
+}
+
+fn assign_ids(todos: &mut [Todo], assigned: &mut HashSet<String>) {
+    for todo in todos {
+        if todo.id.is_empty() {
+            todo.id = todo::new_id();
+            assigned.insert(todo.id.clone());
+        }
+        assign_ids(&mut todo.children, assigned);
+    }
+}
+
+fn remove_ids(todos: &mut [Todo], ids: &HashSet<String>) {
+    for todo in todos {
+        if ids.contains(&todo.id) {
+            todo.id.clear();
+        }
+        remove_ids(&mut todo.children, ids);
+    }
+}
+
+fn collect_tags<'b>(todos: &'b [Todo], tags: &mut Vec<&'b String>) {
+    for todo in todos {
+        tags.extend(todo.tags.iter());
+        collect_tags(&todo.children, tags);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{todos::todo::Priority, utils::date::Date};
+
+    fn names(app: &App) -> Vec<String> {
+        app.visible().map(|row| row.todo.name.clone()).collect()
+    }
+
+    #[test]
+    fn empty_list() {
+        let mut todos = vec![];
+        let mut app = App::with_todos(&mut todos);
+        app.next();
+        app.previous();
+        app.toggle();
+        app.start_deleting();
+        app.confirm_delete();
+        app.move_down();
+        app.undo();
+        assert_eq!(app.state.selected(), None);
+        assert!(app.current().is_none());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut todos = vec![Todo::new("one".into(), None), Todo::new("two".into(), None)];
+        let mut app = App::with_todos(&mut todos);
+
+        app.toggle();
+        app.move_down();
+        assert_eq!(names(&app), vec!["two", "one"]);
+        assert_eq!(app.state.selected(), Some(1));
+        app.start_deleting();
+        app.confirm_delete();
+        assert_eq!(names(&app), vec!["two"]);
+        assert_eq!(app.state.selected(), Some(0));
+
+        app.undo();
+        assert_eq!(names(&app), vec!["two", "one"]);
+        app.undo();
+        assert_eq!(names(&app), vec!["one", "two"]);
+        app.undo();
+        assert!(!app.current().unwrap().is_complete());
+        app.redo();
+        assert!(app.current().unwrap().is_complete());
+
+        // a new change drops everything that could still be redone
+        app.start_adding();
+        if let Mode::Editing(editor) = &mut app.mode {
+            editor.name = crate::todos::input::TextField::single_line("three");
+        }
+        app.submit();
+        app.redo();
+        assert_eq!(names(&app), vec!["one", "two", "three"]);
+        assert_eq!(app.state.selected(), Some(2));
+    }
+
+    #[test]
+    fn sort_and_filter() {
+        let mut todos = vec![
+            Todo::new("one".into(), None),
+            Todo::new("two".into(), None),
+            Todo::new("three".into(), None),
+        ];
+        todos[1].priority = Some(Priority::High);
+        todos[1].tags = vec!["web".into()];
+        todos[2].priority = Some(Priority::Low);
+        todos[2].due = Date::parse("2020-01-31");
+        todos[2].tags = vec!["web".into()];
+        let mut app = App::with_todos(&mut todos);
+
+        app.cycle_sort();
+        assert_eq!(app.sort, Sort::Priority);
+        assert_eq!(names(&app), vec!["two", "three", "one"]);
+        app.cycle_sort();
+        assert_eq!(names(&app), vec!["three", "one", "two"]);
+
+        app.cycle_tag_filter();
+        assert_eq!(app.tag_filter, Some("web".into()));
+        assert_eq!(names(&app), vec!["three", "two"]);
+        app.next();
+        assert_eq!(app.current().unwrap().name, "three");
+        app.toggle();
+        app.cycle_status_filter();
+        assert_eq!(app.status_filter, StatusFilter::Open);
+        assert_eq!(names(&app), vec!["two"]);
+        app.undo();
+        assert_eq!(names(&app), vec!["three", "two"]);
+        assert_eq!(app.current().unwrap().name, "three");
+
+        app.cycle_tag_filter();
+        assert_eq!(app.tag_filter, None);
+    }
+
+    #[test]
+    fn subtasks() {
+        let mut release = Todo::new("release".into(), None);
+        release.children = vec![
+            Todo::new("changelog".into(), None),
+            Todo::new("tag".into(), None),
+        ];
+        let mut todos = vec![release, Todo::new("other".into(), None)];
+        let mut app = App::with_todos(&mut todos);
+        assert_eq!(names(&app), vec!["release", "changelog", "tag", "other"]);
+
+        app.collapse();
+        assert_eq!(names(&app), vec!["release", "other"]);
+        app.expand();
+        app.next();
+        app.move_down();
+        assert_eq!(names(&app), vec!["release", "tag", "changelog", "other"]);
+        app.move_down();
+        assert_eq!(app.current().unwrap().name, "changelog");
+        app.collapse();
+        assert_eq!(app.current().unwrap().name, "release");
+
+        // the parent is done once all of its subtasks are
+        app.next();
+        app.toggle();
+        assert!(!app.todos[0].is_complete());
+        app.next();
+        app.toggle();
+        assert!(app.todos[0].is_complete());
+        assert_eq!(app.todos[0].progress(), Some((2, 2)));
+        app.undo();
+        assert!(!app.todos[0].is_complete());
+        assert_eq!(app.current().unwrap().name, "changelog");
+
+        app.start_adding_subtask();
+        if let Mode::Editing(editor) = &mut app.mode {
+            editor.name = crate::todos::input::TextField::single_line("push");
+        }
+        app.submit();
+        assert_eq!(app.current().unwrap().name, "push");
+        assert_eq!(app.todos[0].get(&[1, 0]).unwrap().name, "push");
+        app.start_deleting();
+        app.confirm_delete();
+        assert_eq!(app.current().unwrap().name, "changelog");
+        assert!(app.todos[0].children[1].children.is_empty());
     }
 
-    pub fn current_mut(&mut self) -> &mut Todo {
-        let i = self.state.selected().unwrap_or(0);
-        self.todos.get_mut(i).unwrap()
+    #[test]
+    fn keep_todos_without_ids() {
+        let mut parent = Todo::new("parent".into(), None);
+        parent.id.clear();
+        parent.children = vec![Todo::new("child".into(), None)];
+        let mut todos = vec![parent];
+        let mut app = App::with_todos(&mut todos);
+        app.collapse();
+        assert_eq!(names(&app), vec!["parent"]);
+        drop(app);
+        assert!(todos[0].id.is_empty());
+        assert!(!todos[0].children[0].id.is_empty());
     }
 }
@@ -0,0 +1,341 @@
+use crate::{
+    todos::todo::{Priority, Todo},
+    utils::date::Date,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A piece of text being edited in the TUI. `cursor` is a byte offset into
+/// `text` and always sits on a char boundary.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TextField {
+    text: String,
+    cursor: usize,
+    multiline: bool,
+}
+
+impl TextField {
+    pub fn single_line<T: Into<String>>(text: T) -> Self {
+        let text = text.into();
+        TextField {
+            cursor: text.len(),
+            text,
+            multiline: false,
+        }
+    }
+
+    pub fn multi_line<T: Into<String>>(text: T) -> Self {
+        TextField {
+            multiline: true,
+            ..TextField::single_line(text)
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The line and column of the cursor, counted in chars.
+    pub fn position(&self) -> (usize, usize) {
+        let before = &self.text[..self.cursor];
+        let line = before.matches('\n').count();
+        let column = before[self.line_start()..].chars().count();
+        (line, column)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        if c == '\n' && !self.multiline {
+            return;
+        }
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = self.line_start();
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.line_end();
+    }
+
+    pub fn up(&mut self) {
+        let start = self.line_start();
+        if start == 0 {
+            return;
+        }
+        let column = self.text[start..self.cursor].chars().count();
+        let prev_start = self.text[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+        self.cursor = self.advance(prev_start, column, start - 1);
+    }
+
+    pub fn down(&mut self) {
+        let end = self.line_end();
+        if end == self.text.len() {
+            return;
+        }
+        let column = self.text[self.line_start()..self.cursor].chars().count();
+        let next_start = end + 1;
+        let next_end = self.text[next_start..]
+            .find('\n')
+            .map_or(self.text.len(), |i| next_start + i);
+        self.cursor = self.advance(next_start, column, next_end);
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.text[self.cursor..]
+            .find('\n')
+            .map_or(self.text.len(), |i| self.cursor + i)
+    }
+
+    /// The offset `column` chars after `from`, without going past `limit`.
+    fn advance(&self, from: usize, column: usize, limit: usize) -> usize {
+        self.text[from..limit]
+            .char_indices()
+            .nth(column)
+            .map_or(limit, |(i, _)| from + i)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Focus {
+    Name,
+    Priority,
+    Due,
+    Tags,
+    Description,
+}
+
+impl Focus {
+    const ORDER: [Focus; 5] = [
+        Focus::Name,
+        Focus::Priority,
+        Focus::Due,
+        Focus::Tags,
+        Focus::Description,
+    ];
+
+    fn step(self, forward: bool) -> Self {
+        let i = Focus::ORDER.iter().position(|f| *f == self).unwrap();
+        let n = Focus::ORDER.len();
+        Focus::ORDER[if forward {
+            (i + 1) % n
+        } else {
+            (i + n - 1) % n
+        }]
+    }
+}
+
+/// The state of the form used to add a todo, or to edit the one at `editing`.
+/// Paths lead through the `children` of the todos, see `App`.
+#[derive(Clone, Debug)]
+pub struct Editor {
+    pub name: TextField,
+    pub priority: TextField,
+    pub due: TextField,
+    pub tags: TextField,
+    pub description: TextField,
+    pub focus: Focus,
+    pub editing: Option<Vec<usize>>,
+    /// The todo a new todo is added to as a subtask.
+    pub parent: Option<Vec<usize>>,
+    /// Why the last attempt to save the todo failed.
+    pub error: Option<String>,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Editor {
+            name: TextField::single_line(""),
+            priority: TextField::single_line(""),
+            due: TextField::single_line(""),
+            tags: TextField::single_line(""),
+            description: TextField::multi_line(""),
+            focus: Focus::Name,
+            editing: None,
+            parent: None,
+            error: None,
+        }
+    }
+
+    pub fn subtask_of(parent: Vec<usize>) -> Self {
+        Editor {
+            parent: Some(parent),
+            ..Editor::new()
+        }
+    }
+
+    pub fn for_todo(path: Vec<usize>, todo: &Todo) -> Self {
+        let to_string = |value: Option<String>| value.unwrap_or_default();
+        Editor {
+            name: TextField::single_line(todo.name.as_str()),
+            priority: TextField::single_line(to_string(todo.priority.map(|p| p.to_string()))),
+            due: TextField::single_line(to_string(todo.due.map(|d| d.to_string()))),
+            tags: TextField::single_line(todo.tags.join(", ")),
+            description: TextField::multi_line(to_string(todo.description.clone())),
+            focus: Focus::Name,
+            editing: Some(path),
+            parent: None,
+            error: None,
+        }
+    }
+
+    pub fn field(&self, focus: Focus) -> &TextField {
+        match focus {
+            Focus::Name => &self.name,
+            Focus::Priority => &self.priority,
+            Focus::Due => &self.due,
+            Focus::Tags => &self.tags,
+            Focus::Description => &self.description,
+        }
+    }
+
+    pub fn field_mut(&mut self) -> &mut TextField {
+        match self.focus {
+            Focus::Name => &mut self.name,
+            Focus::Priority => &mut self.priority,
+            Focus::Due => &mut self.due,
+            Focus::Tags => &mut self.tags,
+            Focus::Description => &mut self.description,
+        }
+    }
+
+    /// Applies a key that edits the text. Pasted text arrives one key at a
+    /// time, so it goes through here as well.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.field_mut().insert(c)
+            }
+            KeyCode::Enter if self.focus == Focus::Description => self.description.insert('\n'),
+            KeyCode::Enter | KeyCode::Tab => self.focus = self.focus.step(true),
+            KeyCode::BackTab => self.focus = self.focus.step(false),
+            KeyCode::Backspace => self.field_mut().backspace(),
+            KeyCode::Delete => self.field_mut().delete(),
+            KeyCode::Left => self.field_mut().left(),
+            KeyCode::Right => self.field_mut().right(),
+            KeyCode::Up => self.field_mut().up(),
+            KeyCode::Down => self.field_mut().down(),
+            KeyCode::Home => self.field_mut().home(),
+            KeyCode::End => self.field_mut().end(),
+            _ => {}
+        }
+    }
+
+    /// Applies a line break that is part of pasted text. The description keeps
+    /// it, the other fields get a space rather than moving on to the next one.
+    pub fn paste_line_break(&mut self) {
+        match self.focus {
+            Focus::Description => self.description.insert('\n'),
+            _ => self.field_mut().insert(' '),
+        }
+    }
+
+    /// Copies what was entered into `todo`, or explains what is wrong with it.
+    pub fn apply_to(&self, todo: &mut Todo) -> Result<(), String> {
+        let name = self.name.text().trim();
+        if name.is_empty() {
+            return Err("a todo needs a name".into());
+        }
+        let priority = match self.priority.text().trim() {
+            "" => None,
+            s => Some(Priority::parse(s).ok_or("the priority should be low, medium or high")?),
+        };
+        let due = match self.due.text().trim() {
+            "" => None,
+            s => Some(Date::parse(s).ok_or("the due date should look like 2020-01-31")?),
+        };
+        let description = self.description.text().trim_end();
+
+        todo.name = name.to_string();
+        todo.description = Some(description.to_string()).filter(|d| !d.is_empty());
+        todo.priority = priority;
+        todo.due = due;
+        todo.tags = self
+            .tags
+            .text()
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches('#').to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn edit_text() {
+        let mut field = TextField::multi_line("héllo\nworld");
+        assert_eq!(field.position(), (1, 5));
+        field.up();
+        assert_eq!(field.position(), (0, 5));
+        field.left();
+        field.left();
+        field.backspace();
+        field.insert('ł');
+        assert_eq!(field.text(), "héłlo\nworld");
+        field.down();
+        assert_eq!(field.position(), (1, 3));
+        field.home();
+        field.delete();
+        field.end();
+        field.insert('\n');
+        assert_eq!(field.text(), "héłlo\norld\n");
+        assert_eq!(field.position(), (2, 0));
+
+        let mut field = TextField::single_line("todo");
+        field.insert('\n');
+        field.up();
+        assert_eq!(field.text(), "todo");
+        assert_eq!(field.position(), (0, 4));
+    }
+
+    #[test]
+    fn paste_line_breaks() {
+        let mut editor = Editor::new();
+        editor.name.insert('a');
+        editor.paste_line_break();
+        editor.name.insert('b');
+        assert_eq!(editor.name.text(), "a b");
+        assert_eq!(editor.focus, Focus::Name);
+
+        editor.focus = Focus::Description;
+        editor.paste_line_break();
+        assert_eq!(editor.description.text(), "\n");
+    }
+}
@@ -1,21 +1,20 @@
 pub mod app;
+mod input;
+pub mod sync;
 pub mod todo;
 mod ui;
 
 use crate::{
     config::project::ProjectConfig,
-    utils::{
-        error::{EnzoError, EnzoErrorKind},
-        query::{AnswerKind, Question},
-    },
+    utils::error::{EnzoError, EnzoErrorKind},
 };
-use app::App;
+use app::{App, Mode};
 use crossterm::{
-    event::{EnableMouseCapture, Event, EventStream, KeyCode},
+    event::{EnableMouseCapture, Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen},
 };
-use futures::{executor::block_on, StreamExt};
+use futures::{executor::block_on, FutureExt, StreamExt};
 use std::{
     fs::File,
     io::{prelude::*, stdout, Write},
@@ -27,7 +26,7 @@ use tui::{
     Terminal,
 };
 
-pub fn start<'a>(todos: &'a mut Vec<Todo>) -> Result<(), EnzoError> {
+pub fn start(todos: &mut Vec<Todo>) -> Result<(), EnzoError> {
     let mut stdout = stdout();
     enable_raw_mode()?;
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -38,7 +37,6 @@ pub fn start<'a>(todos: &'a mut Vec<Todo>) -> Result<(), EnzoError> {
     terminal.clear()?;
 
     let mut app = App::with_todos(todos);
-    app.state.select(Some(0));
 
     block_on(event_listener(&mut terminal, &mut app))?;
 
@@ -57,8 +55,7 @@ pub fn read_from(path: &PathBuf) -> Result<Vec<Todo>, EnzoError> {
     let mut buffer = String::new();
     file.read_to_string(&mut buffer)?;
     let ProjectConfig { todos, .. } = serde_yaml::from_str(buffer.as_str())?;
-    // TODO remove the unwrap
-    Ok(todos.unwrap())
+    Ok(todos.unwrap_or_default())
 }
 
 async fn event_listener<T: Backend>(
@@ -66,33 +63,104 @@ async fn event_listener<T: Backend>(
     app: &mut App<'_>,
 ) -> Result<(), EnzoError> {
     let mut reader = EventStream::new();
+    let mut queued = None;
 
-    terminal.draw(|mut f| ui::draw(&mut f, app))?;
-    while let Some(event) = reader.next().await {
+    draw(terminal, app)?;
+    loop {
+        let event = match queued.take() {
+            Some(event) => event,
+            None => match reader.next().await {
+                Some(event) => event,
+                None => break,
+            },
+        };
         match event {
             Ok(event) => match event {
                 Event::Key(k) => {
-                    if k == KeyCode::Char('q').into() {
+                    if let Mode::Editing(editor) = &mut app.mode {
+                        if k == KeyCode::Esc.into() {
+                            app.cancel_editing();
+                        } else if k == KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL) {
+                            app.submit();
+                        } else if k == KeyCode::Enter.into() {
+                            // pasted text arrives all at once, so a line break
+                            // in it is followed by keys that are already waiting
+                            queued = reader.next().now_or_never().flatten();
+                            if queued.is_some() {
+                                editor.paste_line_break();
+                            } else {
+                                editor.handle_key(k);
+                            }
+                        } else {
+                            editor.handle_key(k);
+                        }
+                    } else if let Mode::ConfirmDelete(_) = app.mode {
+                        if k == KeyCode::Char('y').into() || k == KeyCode::Enter.into() {
+                            app.confirm_delete();
+                        } else {
+                            app.cancel_editing();
+                        }
+                    } else if k == KeyCode::Char('q').into() {
                         break;
                     } else if k == KeyCode::Char('j').into() || k == KeyCode::Down.into() {
                         app.next();
                     } else if k == KeyCode::Char('k').into() || k == KeyCode::Up.into() {
                         app.previous();
+                    } else if k == KeyCode::Char('h').into() || k == KeyCode::Left.into() {
+                        app.collapse();
+                    } else if k == KeyCode::Char('l').into() || k == KeyCode::Right.into() {
+                        app.expand();
+                    } else if k.code == KeyCode::Char('J') {
+                        app.move_down();
+                    } else if k.code == KeyCode::Char('K') {
+                        app.move_up();
                     } else if k == KeyCode::Enter.into() {
-                        if app.current().is_complete() {
-                            app.current_mut().mark_incomplete();
-                        } else {
-                            app.current_mut().mark_complete();
-                        }
+                        app.toggle();
+                    } else if k == KeyCode::Char('a').into() {
+                        app.start_adding();
+                    } else if k.code == KeyCode::Char('A') {
+                        app.start_adding_subtask();
+                    } else if k == KeyCode::Char('e').into() {
+                        app.start_editing();
+                    } else if k == KeyCode::Char('d').into() {
+                        app.start_deleting();
+                    } else if k == KeyCode::Char('s').into() {
+                        app.cycle_status();
+                    } else if k == KeyCode::Char('o').into() {
+                        app.cycle_sort();
+                    } else if k == KeyCode::Char('f').into() {
+                        app.cycle_status_filter();
+                    } else if k == KeyCode::Char('t').into() {
+                        app.cycle_tag_filter();
+                    } else if k == KeyCode::Char('u').into() {
+                        app.undo();
+                    } else if k == KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL) {
+                        app.redo();
                     } else {
                         // TODO
                     }
                 }
-                _ => break,
+                // drawing picks up the new size
+                Event::Resize(_, _) => {}
+                Event::Mouse(_) => continue,
             },
             Err(_) => eprintln!("error"),
         }
-        terminal.draw(|mut f| ui::draw(&mut f, app))?;
+        draw(terminal, app)?;
+    }
+    Ok(())
+}
+
+/// Draws the app and shows the cursor where text is being typed, if any.
+fn draw<T: Backend>(terminal: &mut Terminal<T>, app: &mut App<'_>) -> Result<(), EnzoError> {
+    let mut cursor = None;
+    terminal.draw(|mut f| cursor = ui::draw(&mut f, app))?;
+    match cursor {
+        Some((x, y)) => {
+            terminal.set_cursor(x, y)?;
+            terminal.show_cursor()?;
+        }
+        None => terminal.hide_cursor()?,
     }
     Ok(())
 }
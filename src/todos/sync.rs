@@ -0,0 +1,332 @@
+use crate::{
+    todos::todo::{self, Todo},
+    utils::error::EnzoError,
+};
+use serde_yaml::{Mapping, Value};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Local,
+    Repo,
+}
+
+/// A todo that changed in different ways in the repo and locally since they
+/// were last in sync. A missing side means the todo was removed there.
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub local: Option<Todo>,
+    pub repo: Option<Todo>,
+}
+
+/// Three-way merges the todos in the repo and the local ones, given the todos
+/// in the repo when they were last in sync. Changes to different fields of a
+/// todo are combined and anything else is left to `resolve`.
+///
+/// Todos are matched by id, and subtasks are merged the same way among the
+/// subtasks of their todo. Todos without an id are matched by name and get
+/// one, so the result always has ids.
+pub fn merge<F>(
+    base: &[Todo],
+    repo: &[Todo],
+    local: &[Todo],
+    mut resolve: F,
+) -> Result<Vec<Todo>, EnzoError>
+where
+    F: FnMut(&Conflict) -> Result<Side, EnzoError>,
+{
+    let mut base = base.to_vec();
+    let mut repo = repo.to_vec();
+    let mut local = local.to_vec();
+    assign_ids(&mut [&mut base, &mut repo, &mut local]);
+    merge_lists(&base, &repo, &local, &mut resolve)
+}
+
+fn merge_lists<F>(
+    base: &[Todo],
+    repo: &[Todo],
+    local: &[Todo],
+    resolve: &mut F,
+) -> Result<Vec<Todo>, EnzoError>
+where
+    F: FnMut(&Conflict) -> Result<Side, EnzoError>,
+{
+    // todos stay in the order of the repo, with new local ones at the end
+    let mut ids = repo.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+    for todo in local {
+        if !ids.contains(&todo.id) {
+            ids.push(todo.id.clone());
+        }
+    }
+
+    let mut merged = vec![];
+    for id in ids {
+        let b = base.iter().find(|t| t.id == id);
+        let r = repo.iter().find(|t| t.id == id);
+        let l = local.iter().find(|t| t.id == id);
+        let todo = if r == l || b == l {
+            r.cloned()
+        } else if b == r {
+            l.cloned()
+        } else {
+            match (b, r, l) {
+                (Some(b), Some(r), Some(l)) => Some(merge_fields(b, r, l, resolve)?),
+                _ => {
+                    let conflict = Conflict {
+                        local: l.cloned(),
+                        repo: r.cloned(),
+                    };
+                    match resolve(&conflict)? {
+                        Side::Local => conflict.local,
+                        Side::Repo => conflict.repo,
+                    }
+                }
+            }
+        };
+        merged.extend(todo);
+    }
+    Ok(merged)
+}
+
+/// Merges a todo field by field, going through its serialized form so that
+/// every field is covered, and its subtasks as lists of their own.
+fn merge_fields<F>(
+    base: &Todo,
+    repo: &Todo,
+    local: &Todo,
+    resolve: &mut F,
+) -> Result<Todo, EnzoError>
+where
+    F: FnMut(&Conflict) -> Result<Side, EnzoError>,
+{
+    let children = merge_lists(&base.children, &repo.children, &local.children, resolve)?;
+    let (b, r, l) = (to_mapping(base)?, to_mapping(repo)?, to_mapping(local)?);
+    let completed_at = Value::from("completed_at");
+    let mut keys = r.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+    keys.extend(
+        l.iter()
+            .map(|(k, _)| k.clone())
+            .filter(|k| r.get(k).is_none()),
+    );
+    keys.retain(|k| *k != completed_at);
+
+    let mut merged = Mapping::new();
+    let mut conflicting = vec![];
+    for key in keys {
+        let (bv, rv, lv) = (b.get(&key), r.get(&key), l.get(&key));
+        let value = if rv == lv || bv == lv {
+            rv
+        } else if bv == rv {
+            lv
+        } else {
+            conflicting.push(key.clone());
+            rv
+        };
+        if let Some(value) = value {
+            merged.insert(key, value.clone());
+        }
+    }
+
+    if !conflicting.is_empty() {
+        let conflict = Conflict {
+            local: Some(local.clone()),
+            repo: Some(repo.clone()),
+        };
+        let chosen = match resolve(&conflict)? {
+            Side::Local => &l,
+            Side::Repo => &r,
+        };
+        for key in conflicting {
+            match chosen.get(&key) {
+                Some(value) => merged.insert(key, value.clone()),
+                None => merged.remove(&key),
+            };
+        }
+    }
+
+    // when a todo was completed goes with its status, so completing it on both
+    // sides is not a conflict
+    let status = Value::from("status");
+    let side = if merged.get(&status) == r.get(&status) {
+        &r
+    } else {
+        &l
+    };
+    if let Some(value) = side.get(&completed_at) {
+        merged.insert(completed_at, value.clone());
+    }
+    let mut todo: Todo = serde_yaml::from_value(Value::Mapping(merged))?;
+    todo.children = children;
+    Ok(todo)
+}
+
+/// The fields of a todo, without its subtasks.
+fn to_mapping(todo: &Todo) -> Result<Mapping, EnzoError> {
+    let todo = Todo {
+        children: vec![],
+        ..todo.clone()
+    };
+    match serde_yaml::to_value(&todo)? {
+        Value::Mapping(mapping) => Ok(mapping),
+        _ => unreachable!(),
+    }
+}
+
+/// Gives every todo without an id the id of a todo with the same name in one
+/// of the other lists, or a new one shared by all the lists. Subtasks are
+/// matched among the subtasks of the todos with the same id.
+fn assign_ids(lists: &mut [&mut Vec<Todo>]) {
+    let mut ids = HashMap::new();
+    for todo in lists.iter().flat_map(|list| list.iter()) {
+        if !todo.id.is_empty() {
+            ids.entry(todo.name.clone())
+                .or_insert_with(|| todo.id.clone());
+        }
+    }
+    for list in lists.iter_mut() {
+        let mut used = list
+            .iter()
+            .map(|todo| todo.id.clone())
+            .collect::<HashSet<_>>();
+        for todo in list.iter_mut().filter(|todo| todo.id.is_empty()) {
+            let id = ids
+                .entry(todo.name.clone())
+                .or_insert_with(todo::new_id)
+                .clone();
+            // two todos with the same name in one list are different todos
+            todo.id = if used.contains(&id) {
+                todo::new_id()
+            } else {
+                id
+            };
+            used.insert(todo.id.clone());
+        }
+    }
+
+    let ids = lists
+        .iter()
+        .flat_map(|list| list.iter().map(|todo| todo.id.clone()))
+        .collect::<HashSet<_>>();
+    for id in ids {
+        let mut children = lists
+            .iter_mut()
+            .filter_map(|list| list.iter_mut().find(|todo| todo.id == id))
+            .map(|todo| &mut todo.children)
+            .collect::<Vec<_>>();
+        assign_ids(&mut children);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn todo(id: &str, name: &str) -> Todo {
+        Todo {
+            id: id.into(),
+            ..Todo::new(name.into(), None)
+        }
+    }
+
+    fn names(todos: &[Todo]) -> Vec<&str> {
+        todos.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    fn no_conflicts(_: &Conflict) -> Result<Side, EnzoError> {
+        panic!("unexpected conflict")
+    }
+
+    #[test]
+    fn merge_changes_from_both_sides() {
+        let base = vec![todo("a", "one"), todo("b", "two"), todo("c", "three")];
+        // the repo renamed one, removed two and added four
+        let mut repo = vec![todo("a", "uno"), todo("c", "three"), todo("d", "four")];
+        // locally one was completed and five was added
+        let mut local = base.clone();
+        local[0].mark_complete();
+        local.push(todo("e", "five"));
+
+        let merged = merge(&base, &repo, &local, no_conflicts).unwrap();
+        assert_eq!(names(&merged), vec!["uno", "three", "four", "five"]);
+        assert!(merged[0].is_complete());
+
+        // both removing a todo or making the same change is not a conflict
+        repo.retain(|t| t.id != "c");
+        local.retain(|t| t.id != "c");
+        local[0].name = "uno".into();
+        let merged = merge(&base, &repo, &local, no_conflicts).unwrap();
+        assert_eq!(names(&merged), vec!["uno", "four", "five"]);
+    }
+
+    #[test]
+    fn resolve_conflicts() {
+        let base = vec![todo("a", "one"), todo("b", "two")];
+        let repo = vec![todo("a", "uno"), todo("b", "dos")];
+        let mut local = vec![todo("a", "eins")];
+        local[0].description = Some("first".into());
+
+        let mut conflicts = 0;
+        let merged = merge(&base, &repo, &local, |conflict| {
+            conflicts += 1;
+            Ok(if conflict.local.is_some() {
+                Side::Local
+            } else {
+                Side::Repo
+            })
+        })
+        .unwrap();
+        assert_eq!(conflicts, 2);
+        assert_eq!(names(&merged), vec!["eins", "dos"]);
+        assert_eq!(merged[0].description, Some("first".into()));
+    }
+
+    #[test]
+    fn match_todos_without_ids_by_name() {
+        let base = vec![todo("", "one")];
+        let repo = vec![todo("", "one"), todo("", "two")];
+        let mut local = vec![todo("a", "one")];
+        local[0].mark_complete();
+
+        let merged = merge(&base, &repo, &local, no_conflicts).unwrap();
+        assert_eq!(names(&merged), vec!["one", "two"]);
+        assert_eq!(merged[0].id, "a");
+        assert!(merged[0].is_complete());
+        assert!(!merged[1].id.is_empty());
+    }
+
+    #[test]
+    fn complete_on_both_sides() {
+        let base = vec![todo("a", "one")];
+        let mut repo = base.clone();
+        repo[0].mark_complete();
+        repo[0].completed_at = Some("2020-01-31T10:00:00Z".into());
+        let mut local = base.clone();
+        local[0].mark_complete();
+        local[0].completed_at = Some("2020-02-01T10:00:00Z".into());
+        local[0].description = Some("done twice".into());
+
+        let merged = merge(&base, &repo, &local, no_conflicts).unwrap();
+        assert!(merged[0].is_complete());
+        assert_eq!(merged[0].completed_at, repo[0].completed_at);
+        assert_eq!(merged[0].description, local[0].description);
+    }
+
+    #[test]
+    fn merge_subtasks() {
+        let mut parent = todo("a", "one");
+        parent.children = vec![todo("b", "first"), todo("c", "second")];
+        let base = vec![parent];
+        // the repo completed the first subtask, locally the second was renamed
+        let mut repo = base.clone();
+        repo[0].children[0].mark_complete();
+        let mut local = base.clone();
+        local[0].children[1].name = "last".into();
+        local[0].children.push(todo("", "third"));
+
+        let merged = merge(&base, &repo, &local, no_conflicts).unwrap();
+        let children = &merged[0].children;
+        assert_eq!(names(children), vec!["first", "last", "third"]);
+        assert!(children[0].is_complete());
+        assert!(children.iter().all(|t| !t.id.is_empty()));
+    }
+}
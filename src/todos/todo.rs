@@ -1,35 +1,296 @@
+use crate::utils::date::{self, Date};
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+}
+
+impl Status {
+    pub const ALL: [Status; 4] = [
+        Status::Todo,
+        Status::InProgress,
+        Status::Blocked,
+        Status::Done,
+    ];
+
+    /// The status after this one, for cycling through them with a single key.
+    pub fn next(self) -> Self {
+        match self {
+            Status::Todo => Status::InProgress,
+            Status::InProgress => Status::Blocked,
+            Status::Blocked => Status::Done,
+            Status::Done => Status::Todo,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in progress",
+            Status::Blocked => "blocked",
+            Status::Done => "done",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "low" | "l" => Some(Priority::Low),
+            "medium" | "m" => Some(Priority::Medium),
+            "high" | "h" => Some(Priority::High),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawTodo", into = "RawTodo")]
 pub struct Todo {
+    /// Identifies the todo across copies of it, like the one in `enzo.yaml`
+    /// and the one in the global config. Todos written by older versions of
+    /// enzo have none until they are synced.
+    pub id: String,
     pub name: String,
     pub description: Option<String>,
-    #[serde(default)]
-    pub completed: Option<bool>,
+    pub status: Status,
+    pub priority: Option<Priority>,
+    pub due: Option<Date>,
+    pub tags: Vec<String>,
+    pub created_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub children: Vec<Todo>,
+}
+
+/// How a todo is written to yaml. `completed` is what older versions of enzo
+/// used instead of `status`, so it is still read, and written for done todos
+/// so that those versions see them as complete.
+#[derive(Serialize, Deserialize)]
+struct RawTodo {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    id: String,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<Status>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<Date>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<Todo>,
+}
+
+impl From<RawTodo> for Todo {
+    fn from(raw: RawTodo) -> Self {
+        let status = match (raw.status, raw.completed) {
+            (Some(status), _) => status,
+            (None, Some(true)) => Status::Done,
+            (None, _) => Status::Todo,
+        };
+        Todo {
+            id: raw.id,
+            name: raw.name,
+            description: raw.description,
+            status,
+            priority: raw.priority,
+            due: raw.due,
+            tags: raw.tags,
+            created_at: raw.created_at,
+            completed_at: raw.completed_at,
+            children: raw.children,
+        }
+    }
+}
+
+impl From<Todo> for RawTodo {
+    fn from(todo: Todo) -> Self {
+        let status = todo.status;
+        RawTodo {
+            id: todo.id,
+            name: todo.name,
+            description: todo.description,
+            status: Some(status).filter(|s| *s != Status::Todo),
+            completed: Some(true).filter(|_| status == Status::Done),
+            priority: todo.priority,
+            due: todo.due,
+            tags: todo.tags,
+            created_at: todo.created_at,
+            completed_at: todo.completed_at,
+            children: todo.children,
+        }
+    }
 }
 
 impl Todo {
     pub fn new(name: String, description: Option<String>) -> Self {
         Self {
+            id: new_id(),
             name,
             description,
-            completed: Some(false),
+            status: Status::Todo,
+            priority: None,
+            due: None,
+            tags: vec![],
+            created_at: Some(date::now()),
+            completed_at: None,
+            children: vec![],
+        }
+    }
+
+    /// Changes the status, keeping track of when the todo was completed.
+    pub fn set_status(&mut self, status: Status) {
+        if status == Status::Done && self.status != Status::Done {
+            self.completed_at = Some(date::now());
+        } else if status != Status::Done {
+            self.completed_at = None;
         }
+        self.status = status;
     }
 
     pub fn mark_complete(&mut self) {
-        self.completed = Some(true);
+        self.set_status(Status::Done);
     }
 
     pub fn mark_incomplete(&mut self) {
-        self.completed = Some(false);
+        self.set_status(Status::Todo);
     }
 
     pub fn is_complete(&self) -> bool {
-        if let Some(val) = self.completed {
-            val
-        } else {
-            false
+        self.status == Status::Done
+    }
+
+    /// How many of the direct children are done, out of how many there are.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.children.is_empty() {
+            return None;
+        }
+        let done = self.children.iter().filter(|c| c.is_complete()).count();
+        Some((done, self.children.len()))
+    }
+
+    /// Completes the todo once all of its children are done, and reopens it
+    /// when one of them is not anymore.
+    pub fn update_from_children(&mut self) {
+        match self.progress() {
+            Some((done, total)) if done == total && !self.is_complete() => self.mark_complete(),
+            Some((done, total)) if done < total && self.is_complete() => self.mark_incomplete(),
+            _ => {}
         }
     }
+
+    pub fn get(&self, path: &[usize]) -> Option<&Todo> {
+        match path.split_first() {
+            Some((i, rest)) => self.children.get(*i)?.get(rest),
+            None => Some(self),
+        }
+    }
+
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut Todo> {
+        match path.split_first() {
+            Some((i, rest)) => self.children.get_mut(*i)?.get_mut(rest),
+            None => Some(self),
+        }
+    }
+
+    pub fn is_overdue(&self) -> bool {
+        !self.is_complete() && self.due.is_some_and(|due| due < Date::today())
+    }
+}
+
+/// A random id that is short enough to not clutter `enzo.yaml`.
+pub fn new_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    format!("{:012x}", hasher.finish() >> 16)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_old_and_new_todos() {
+        let todos: Vec<Todo> = serde_yaml::from_str(
+            "- name: old\n  completed: true\n- name: older\n- name: new\n  status: blocked\n  priority: high\n  due: 2020-01-31\n  tags: [web]\n",
+        )
+        .unwrap();
+        assert_eq!(todos[0].status, Status::Done);
+        assert_eq!(todos[1].status, Status::Todo);
+        assert_eq!(todos[2].status, Status::Blocked);
+        assert_eq!(todos[2].priority, Some(Priority::High));
+        assert_eq!(todos[2].due, Date::new(2020, 1, 31));
+        assert_eq!(todos[2].tags, vec!["web"]);
+
+        let yaml = serde_yaml::to_string(&todos).unwrap();
+        assert!(yaml.contains("status: done\n  completed: true"));
+        assert!(yaml.contains("due: 2020-01-31"));
+        let again: Vec<Todo> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(again, todos);
+    }
+
+    #[test]
+    fn subtasks() {
+        let mut todo: Todo = serde_yaml::from_str(
+            "name: release\nchildren:\n  - name: changelog\n    completed: true\n  - name: tag\n    children:\n      - name: push\n",
+        )
+        .unwrap();
+        assert_eq!(todo.progress(), Some((1, 2)));
+        assert_eq!(todo.get(&[1, 0]).unwrap().name, "push");
+        assert!(todo.get(&[2]).is_none());
+
+        todo.get_mut(&[1]).unwrap().mark_complete();
+        todo.update_from_children();
+        assert!(todo.is_complete());
+        todo.get_mut(&[0]).unwrap().mark_incomplete();
+        todo.update_from_children();
+        assert!(!todo.is_complete());
+    }
 }
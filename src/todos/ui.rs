@@ -1,13 +1,18 @@
-use crate::todos::app::App;
+use crate::todos::{
+    app::{App, Mode, Sort, StatusFilter},
+    input::{Editor, Focus},
+    todo::{Status, Todo},
+};
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     widgets::{Block, Borders, List, Paragraph, Text},
     Frame,
 };
 
-pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+/// Draws the app and returns where the cursor should be shown, if anywhere.
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) -> Option<(u16, u16)> {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -21,45 +26,82 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         .split(f.size());
 
     // draw todos
-    let items = app.todos.iter().map(|i| {
-        if i.completed.unwrap_or_default() {
+    let items = app.visible().map(|row| {
+        let i = row.todo;
+        let fold = match (i.children.is_empty(), row.collapsed) {
+            (true, _) => "  ",
+            (false, true) => "▸ ",
+            (false, false) => "▾ ",
+        };
+        let text = format!(
+            "{}{}{} {}{}",
+            "  ".repeat(row.depth),
+            fold,
+            marker(i.status),
+            i.name,
+            summary(i)
+        );
+        if i.is_complete() {
             Text::styled(
-                i.name.clone(),
+                text,
                 Style::new().modifier(Modifier::DIM | Modifier::CROSSED_OUT),
             )
+        } else if i.is_overdue() {
+            Text::styled(text, Style::new().fg(Color::Red))
         } else {
-            Text::raw(i.name.clone())
+            Text::raw(text)
         }
     });
+    let items = items.collect::<Vec<_>>();
+    let mut title = String::from("todos");
+    if app.sort != Sort::Manual {
+        title.push_str(&format!(" · by {}", app.sort));
+    }
+    match app.status_filter {
+        StatusFilter::All => {}
+        StatusFilter::Open => title.push_str(" · open"),
+        StatusFilter::Only(status) => title.push_str(&format!(" · {}", status)),
+    }
+    if let Some(tag) = &app.tag_filter {
+        title.push_str(&format!(" · #{}", tag));
+    }
     let style = Style::default();
-    let items = List::new(items)
+    let items = List::new(items.into_iter())
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("todos")
+                .title(&title)
                 .title_style(Style::new().fg(Color::Yellow).modifier(Modifier::BOLD)),
         )
         .style(style)
         .highlight_style(style.fg(Color::Blue).modifier(Modifier::BOLD))
         .highlight_symbol(">>= ");
 
+    f.render_stateful_widget(items, chunks[0], &mut app.state);
+    if let Mode::Editing(editor) = &app.mode {
+        return draw_editor(f, editor, chunks[1], chunks[2]);
+    }
+
     // draw description
-    let status = if app.current().is_complete() {
-        Text::styled(
-            "\ncomplete\n\n",
-            Style::new().fg(Color::Green).modifier(Modifier::BOLD),
-        )
-    } else {
-        Text::styled(
-            "\nincomplete\n\n",
-            Style::new().fg(Color::Red).modifier(Modifier::BOLD),
-        )
-    };
-    let mut description = String::new();
-    if let Some(s) = app.current().description.as_ref() {
-        description.push_str(&s.clone())
+    let text = match app.current() {
+        Some(todo) => {
+            let color = match todo.status {
+                Status::Todo => Color::Red,
+                Status::InProgress => Color::Yellow,
+                Status::Blocked => Color::Magenta,
+                Status::Done => Color::Green,
+            };
+            let status = Text::styled(
+                format!("\n{}\n\n", todo.status),
+                Style::new().fg(color).modifier(Modifier::BOLD),
+            );
+            [status, Text::raw(details(todo))]
+        }
+        None => [
+            Text::styled("\nnothing to do\n\n", Style::new().modifier(Modifier::BOLD)),
+            Text::raw("press a to add a todo"),
+        ],
     };
-    let text = [status, Text::raw(format!("{}", description))];
     let current_description = Paragraph::new(text.iter())
         .block(
             Block::default()
@@ -67,16 +109,28 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                 .title("description")
                 .title_style(Style::new().fg(Color::Magenta).modifier(Modifier::BOLD)),
         )
-        .style(style);
+        .style(style)
+        .wrap(true);
 
     // draw help
-    let help_text = [
-        Text::raw("down arrow / j - next todo\n"),
-        Text::raw("up arrow / k - previous todo\n"),
-        Text::raw("enter - check/uncheck todo\n"),
-        Text::raw("a - add todo\n"),
-        Text::raw("d - remove todo\n"),
-    ];
+    let confirm;
+    let help_text = match (&app.mode, app.current()) {
+        (Mode::ConfirmDelete(_), Some(todo)) => {
+            confirm = format!("delete `{}`? (y/n)\n", todo.name);
+            vec![Text::styled(
+                confirm.as_str(),
+                Style::new().fg(Color::Red).modifier(Modifier::BOLD),
+            )]
+        }
+        _ => vec![
+            Text::raw("j / k - next/previous todo    J / K - move todo down/up\n"),
+            Text::raw("h / l - collapse/expand subtasks    enter - check/uncheck todo\n"),
+            Text::raw("a - add todo    A - add subtask    e - edit todo    d - remove todo\n"),
+            Text::raw("s - change status    "),
+            Text::raw("o - change order    f - filter by status    t - filter by tag\n"),
+            Text::raw("u / ctrl+r - undo/redo    q - quit\n"),
+        ],
+    };
     let help = Paragraph::new(help_text.iter())
         .block(
             Block::default()
@@ -86,7 +140,159 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         )
         .style(style);
 
-    f.render_stateful_widget(items, chunks[0], &mut app.state);
     f.render_widget(current_description, chunks[1]);
     f.render_widget(help, chunks[2]);
+    None
+}
+
+fn marker(status: Status) -> &'static str {
+    match status {
+        Status::Todo => "[ ]",
+        Status::InProgress => "[~]",
+        Status::Blocked => "[!]",
+        Status::Done => "[x]",
+    }
+}
+
+/// The progress of the subtasks, the priority, due date and tags of a todo, to show next to its name.
+fn summary(todo: &Todo) -> String {
+    let mut s = String::new();
+    if let Some((done, total)) = todo.progress() {
+        s.push_str(&format!("  {}/{}", done, total));
+    }
+    if let Some(priority) = todo.priority {
+        s.push_str(&format!("  !{}", priority));
+    }
+    if let Some(due) = todo.due {
+        s.push_str(&format!("  due {}", due));
+    }
+    for tag in &todo.tags {
+        s.push_str(&format!("  #{}", tag));
+    }
+    s
+}
+
+fn details(todo: &Todo) -> String {
+    let mut s = String::new();
+    let fields = [
+        (
+            "subtasks",
+            todo.progress()
+                .map(|(done, total)| format!("{} of {} done", done, total)),
+        ),
+        ("priority", todo.priority.map(|p| p.to_string())),
+        ("due", todo.due.map(|d| d.to_string())),
+        ("created", todo.created_at.clone()),
+        ("completed", todo.completed_at.clone()),
+    ];
+    for (name, value) in fields.iter() {
+        if let Some(value) = value {
+            s.push_str(&format!("{}: {}\n", name, value));
+        }
+    }
+    if !todo.tags.is_empty() {
+        s.push_str(&format!("tags: #{}\n", todo.tags.join(" #")));
+    }
+    if let Some(description) = &todo.description {
+        if !s.is_empty() {
+            s.push('\n');
+        }
+        s.push_str(description);
+    }
+    s
+}
+
+fn draw_editor<B: Backend>(
+    f: &mut Frame<B>,
+    editor: &Editor,
+    area: Rect,
+    help_area: Rect,
+) -> Option<(u16, u16)> {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Min(3),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let row = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Percentage(50),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[1]);
+    let title = match (&editor.editing, &editor.parent) {
+        (Some(_), _) => "edit todo",
+        (None, Some(_)) => "new subtask",
+        (None, None) => "new todo",
+    };
+    let fields = [
+        (title, Focus::Name, chunks[0]),
+        ("priority (low/medium/high)", Focus::Priority, row[0]),
+        ("due (yyyy-mm-dd)", Focus::Due, row[1]),
+        ("tags (comma separated)", Focus::Tags, row[2]),
+        ("description", Focus::Description, chunks[2]),
+    ];
+
+    let mut cursor = None;
+    for (title, focus, area) in fields.iter() {
+        let field = editor.field(*focus);
+        let focused = editor.focus == *focus;
+        let (line, column) = field.position();
+        // keep the line with the cursor in view
+        let height = (area.height.saturating_sub(2) as usize).max(1);
+        let scroll = (line + 1).saturating_sub(height);
+        if focused {
+            let x = area.x + 1 + column.min(area.width.saturating_sub(3) as usize) as u16;
+            let y = area.y + 1 + (line - scroll) as u16;
+            cursor = Some((x, y));
+        }
+
+        let color = if focused { Color::Blue } else { Color::White };
+        let text = [Text::raw(field.text())];
+        let widget = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::new().fg(color))
+                    .title(title)
+                    .title_style(Style::new().fg(color).modifier(Modifier::BOLD)),
+            )
+            .wrap(false)
+            .scroll(scroll as u16);
+        f.render_widget(widget, *area);
+    }
+
+    let error;
+    let mut help_text = vec![];
+    if let Some(e) = &editor.error {
+        error = format!("{}\n", e);
+        help_text.push(Text::styled(
+            error.as_str(),
+            Style::new().fg(Color::Red).modifier(Modifier::BOLD),
+        ));
+    }
+    help_text.extend(vec![
+        Text::raw("tab / shift+tab - next/previous field\n"),
+        Text::raw("enter - next field, or next line of the description\n"),
+        Text::raw("ctrl+s - save todo\n"),
+        Text::raw("esc - cancel\n"),
+    ]);
+    let help = Paragraph::new(help_text.iter()).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("help")
+            .title_style(Style::new().fg(Color::White).modifier(Modifier::BOLD)),
+    );
+    f.render_widget(help, help_area);
+    cursor
 }
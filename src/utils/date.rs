@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A calendar day, written as `YYYY-MM-DD`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    year: i64,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    pub fn new(year: i64, month: u32, day: u32) -> Option<Self> {
+        if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Date { year, month, day })
+    }
+
+    /// Today in UTC.
+    pub fn today() -> Self {
+        let (date, _) = split_timestamp(unix_now());
+        date
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.trim().splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        Date::new(year, month, day)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Date::parse(&s).ok_or_else(|| format!("Expected a date like 2020-01-31. Found: {}", s))
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+/// The current time in UTC as an RFC 3339 timestamp, e.g. `2020-01-31T13:37:00Z`.
+pub fn now() -> String {
+    let secs = unix_now();
+    let (date, secs) = split_timestamp(secs);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        date,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Splits seconds since the epoch into a date and the seconds into that day.
+fn split_timestamp(secs: i64) -> (Date, i64) {
+    let days = secs.div_euclid(86400);
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (Date { year, month, day }, secs.rem_euclid(86400))
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(split_timestamp(0), (Date::new(1970, 1, 1).unwrap(), 0));
+        assert_eq!(
+            split_timestamp(951_782_400 + 61),
+            (Date::new(2000, 2, 29).unwrap(), 61)
+        );
+        assert_eq!(Date::parse("2020-02-30"), None);
+        assert_eq!(Date::parse("2020-13-01"), None);
+        assert_eq!(Date::parse("tomorrow"), None);
+        let date = Date::parse("2020-1-5").unwrap();
+        assert_eq!(date.to_string(), "2020-01-05");
+        assert!(date < Date::parse("2020-01-31").unwrap());
+    }
+}
@@ -34,6 +34,18 @@ impl From<serde_yaml::Error> for EnzoError {
     }
 }
 
+impl From<serde_json::Error> for EnzoError {
+    fn from(error: serde_json::Error) -> Self {
+        EnzoError::new(format!("{}", error), EnzoErrorKind::ParseError)
+    }
+}
+
+impl From<git2::Error> for EnzoError {
+    fn from(error: git2::Error) -> Self {
+        EnzoError::new(error.message(), EnzoErrorKind::GitError)
+    }
+}
+
 impl From<crossterm::ErrorKind> for EnzoError {
     fn from(error: crossterm::ErrorKind) -> Self {
         EnzoError::new(format!("{:?}", error), EnzoErrorKind::TerminalError)
@@ -1,10 +1,14 @@
+pub mod date;
 pub mod error;
 pub mod query;
 
 use clap::ArgMatches;
-use dirs::home_dir;
+use dirs::{data_dir, home_dir};
 use error::{EnzoError, EnzoErrorKind};
-use std::path::PathBuf;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
 
 pub fn get_home_dir() -> Result<PathBuf, EnzoError> {
     match home_dir() {
@@ -16,6 +20,125 @@ pub fn get_home_dir() -> Result<PathBuf, EnzoError> {
     }
 }
 
+/// The directory enzo keeps its own data in, e.g. the template cache.
+pub fn get_data_dir() -> Result<PathBuf, EnzoError> {
+    match data_dir() {
+        Some(mut path) => {
+            path.push("enzo");
+            Ok(path)
+        }
+        None => Err(EnzoError::new(
+            "Couldn't access the data directory".to_string(),
+            EnzoErrorKind::FatalError,
+        )),
+    }
+}
+
+/// Expands a leading `~` to the home directory and `$VAR` or `${VAR}` to the
+/// value of the environment variable. Variables that aren't set are left as is.
+pub fn expand_path(path: &Path) -> PathBuf {
+    let path = match path.to_str() {
+        Some(path) => path,
+        None => return path.to_path_buf(),
+    };
+    let mut expanded = String::new();
+    let mut rest = path;
+    if rest == "~" || rest.starts_with("~/") {
+        if let Some(home) = home_dir() {
+            expanded.push_str(&home.to_string_lossy());
+            rest = &rest[1..];
+        }
+    }
+    while let Some(i) = rest.find('$') {
+        expanded.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        let (name, len) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        match env::var(name) {
+            Ok(value) if !name.is_empty() => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[i..i + 1 + len]),
+        }
+        rest = &after[len..];
+    }
+    expanded.push_str(rest);
+    PathBuf::from(expanded)
+}
+
+/// Replaces the home directory at the start of `path` with `~` so the path
+/// stays valid on machines with a different home directory.
+pub fn contract_path(path: &Path) -> PathBuf {
+    match home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) if rest.as_os_str().is_empty() => PathBuf::from("~"),
+        Some(rest) => Path::new("~").join(rest),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Moves the directory `from` to `to`. `fs::rename` can't move it to another
+/// filesystem, e.g. a mounted drive, so then it is copied and removed instead.
+pub fn move_dir(from: &Path, to: &Path) -> Result<(), EnzoError> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            info(format!(
+                "{:?} is on another filesystem, copying it there",
+                to
+            ));
+            if let Err(e) = copy_dir(from, to) {
+                // don't leave half a copy behind, the original is still there
+                let _ = fs::remove_dir_all(to);
+                return Err(e.into());
+            }
+            fs::remove_dir_all(from)?;
+            Ok(())
+        }
+        res => Ok(res?),
+    }
+}
+
+/// Copies the directory `from` to `to`, which must not exist, keeping
+/// permissions and symlinks as they are.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    // only now, in case `from` is read-only
+    fs::set_permissions(to, fs::metadata(from)?.permissions())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    let target = fs::read_link(from)?;
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
 pub fn get<'a>(key: &'a str, input: &'a ArgMatches) -> Result<&'a str, EnzoError> {
     match input.value_of(key) {
         Some(val) => Ok(val),
@@ -26,8 +149,10 @@ pub fn get<'a>(key: &'a str, input: &'a ArgMatches) -> Result<&'a str, EnzoError
     }
 }
 
+// messages go to stderr so that they don't end up in output that is captured,
+// like the path printed by `enzo <workspace>`
 pub fn warning<T: AsRef<str>>(msg: T) {
-    println!(
+    eprintln!(
         "{} {}",
         ansi_term::Color::Yellow.bold().paint("[warning]"),
         msg.as_ref()
@@ -35,7 +160,7 @@ pub fn warning<T: AsRef<str>>(msg: T) {
 }
 
 pub fn info<T: AsRef<str>>(msg: T) {
-    println!(
+    eprintln!(
         "{} {}",
         ansi_term::Color::Blue.bold().paint("[info]"),
         msg.as_ref()
@@ -43,9 +168,75 @@ pub fn info<T: AsRef<str>>(msg: T) {
 }
 
 pub fn success<T: AsRef<str>>(msg: T) {
-    println!(
+    eprintln!(
         "{} {} 🎉",
         ansi_term::Color::Green.bold().paint("[success]"),
         msg.as_ref()
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expand_and_contract() {
+        let home = home_dir().unwrap();
+        env::set_var("ENZO_TEST_DEV", "/mnt/dev");
+
+        assert_eq!(expand_path(Path::new("~/dev")), home.join("dev"));
+        assert_eq!(
+            expand_path(Path::new("$ENZO_TEST_DEV/hackgt")),
+            PathBuf::from("/mnt/dev/hackgt")
+        );
+        assert_eq!(
+            expand_path(Path::new("${ENZO_TEST_DEV}x/y")),
+            PathBuf::from("/mnt/devx/y")
+        );
+        assert_eq!(
+            expand_path(Path::new("/a/$ENZO_TEST_UNSET/b")),
+            PathBuf::from("/a/$ENZO_TEST_UNSET/b")
+        );
+        assert_eq!(
+            expand_path(Path::new("/a/${oops")),
+            PathBuf::from("/a/${oops")
+        );
+        assert_eq!(expand_path(Path::new("dev/~x")), PathBuf::from("dev/~x"));
+
+        assert_eq!(
+            contract_path(&home.join("dev/hackgt")),
+            PathBuf::from("~/dev/hackgt")
+        );
+        assert_eq!(contract_path(&home), PathBuf::from("~"));
+        assert_eq!(
+            contract_path(Path::new("/mnt/dev")),
+            PathBuf::from("/mnt/dev")
+        );
+        assert_eq!(
+            expand_path(&contract_path(&home.join("dev"))),
+            home.join("dev")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dirs() {
+        let dir = env::temp_dir().join(format!("enzo-copy-{}", std::process::id()));
+        let from = dir.join("from");
+        fs::create_dir_all(from.join("src")).unwrap();
+        fs::write(from.join("src/main.rs"), "fn main() {}").unwrap();
+        std::os::unix::fs::symlink("src/main.rs", from.join("link")).unwrap();
+
+        let to = dir.join("to");
+        copy_dir(&from, &to).unwrap();
+        assert_eq!(
+            fs::read_to_string(to.join("src/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert_eq!(
+            fs::read_link(to.join("link")).unwrap(),
+            PathBuf::from("src/main.rs")
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,15 @@
+use super::error::EnzoError;
 use ansi_term::Color::{Green, White, Yellow};
-use read_input::prelude::*;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, Write},
+};
 
 pub struct Question<'a> {
     pub question: &'a str,
@@ -46,14 +54,49 @@ impl<'a> Question<'a> {
 
     pub fn ask(&self, answer: &mut AnswerKind) {
         match answer {
+            // the question goes to stderr like every other message, so that it
+            // shows even when stdout is captured
             AnswerKind::Single(ref mut s) => {
-                let q = self.to_string();
-                let answer = input::<String>().msg(q).get();
-                *s = answer;
+                eprint!("{}", self);
+                let _ = io::stderr().flush();
+                let mut line = String::new();
+                let _ = io::stdin().read_line(&mut line);
+                *s = line.trim_end_matches(['\r', '\n']).to_string();
             }
             _ => unimplemented!(),
         }
     }
+
+    /// Asks for a secret like a password without echoing what is typed.
+    /// Returns `None` if the question was dismissed with esc or ctrl+c.
+    pub fn ask_hidden(&self) -> Result<Option<String>, EnzoError> {
+        eprint!("{}", self);
+        io::stderr().flush()?;
+        enable_raw_mode()?;
+        let mut secret = String::new();
+        let res = loop {
+            let key = match event::read() {
+                Ok(Event::Key(key)) => key,
+                Ok(_) => continue,
+                Err(e) => break Err(e),
+            };
+            match key.code {
+                KeyCode::Enter => break Ok(Some(secret)),
+                KeyCode::Esc => break Ok(None),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Ok(None)
+                }
+                KeyCode::Char(c) => secret.push(c),
+                KeyCode::Backspace => {
+                    secret.pop();
+                }
+                _ => {}
+            }
+        };
+        disable_raw_mode()?;
+        eprintln!();
+        Ok(res?)
+    }
 }
 
 impl<'a> fmt::Display for Question<'a> {
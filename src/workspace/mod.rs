@@ -1,19 +1,27 @@
 pub mod project;
 
-use crate::utils::{self, query::Question};
+use crate::utils::{
+    self,
+    error::EnzoError,
+    query::{AnswerKind, Question},
+};
 use crate::workspace::project::Project;
-use read_input::prelude::*;
+use crate::{git, todos};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How many directories deep `scan` looks for repos.
+const SCAN_DEPTH: usize = 5;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct WorkspaceData {
     pub path: PathBuf,
-    pub projects: Vec<Project>,
+    pub projects: Vec<(PathBuf, Project)>,
 }
 
 impl WorkspaceData {
-    pub fn new(path: PathBuf, projects: Vec<Project>) -> WorkspaceData {
+    pub fn new(path: PathBuf, projects: Vec<(PathBuf, Project)>) -> WorkspaceData {
         WorkspaceData { path, projects }
     }
 }
@@ -21,6 +29,33 @@ impl WorkspaceData {
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct WorkspaceName(pub String);
 
+impl WorkspaceName {
+    /// Whether this workspace is nested under `parent` by name, e.g.
+    /// `hackgt.websites` under `hackgt` or `college/hw` under `college`.
+    pub fn is_nested_in(&self, parent: &WorkspaceName) -> bool {
+        let (name, parent) = (&self.0, &parent.0);
+        name.len() > parent.len()
+            && name.starts_with(parent.as_str())
+            && name[parent.len()..].starts_with(['.', '/'])
+    }
+
+    /// The name this workspace gets when `from` is renamed to `to`, if it is
+    /// `from` or nested under it.
+    pub fn renamed(&self, from: &WorkspaceName, to: &WorkspaceName) -> Option<WorkspaceName> {
+        if self == from {
+            Some(to.clone())
+        } else if self.is_nested_in(from) {
+            Some(WorkspaceName(format!(
+                "{}{}",
+                to.0,
+                &self.0[from.0.len()..]
+            )))
+        } else {
+            None
+        }
+    }
+}
+
 impl From<String> for WorkspaceName {
     fn from(name: String) -> Self {
         WorkspaceName(name)
@@ -34,36 +69,150 @@ impl From<&str> for WorkspaceName {
 }
 
 pub fn query_workspace() -> Result<(WorkspaceName, WorkspaceData), utils::error::EnzoError> {
-    let name = input::<String>()
-        .msg(format!("{}", Question::new_question("Workspace name")))
-        .get();
+    let name = ask(Question::new_question("Workspace name"));
 
-    println!();
+    eprintln!();
 
     let home = utils::get_home_dir()?;
-    let mut ext;
     let mut base;
 
     loop {
         base = home.clone();
-        ext = input::<String>()
-            .msg(format!(
-                "{}",
-                Question::new("Path to workspace", Some("$HOME"), Some("$HOME/"), None)
-            ))
-            .get();
-        base.push(ext);
+        base.push(ask(Question::new(
+            "Path to workspace",
+            Some("$HOME"),
+            Some("$HOME/"),
+            None,
+        )));
         if !base.exists() {
-            println!("Hmmm. looks like that wasn't a valid path. Try again\n");
+            eprintln!("Hmmm. looks like that wasn't a valid path. Try again\n");
         } else {
             break;
         }
     }
-    // println!("{:?}", base);
-    println!();
+    eprintln!();
+
+    let name = WorkspaceName(name);
+    let projects = scan(&name, &base, &[])?;
+    let data = WorkspaceData::new(base, projects);
+
+    Ok((name, data))
+}
+
+fn ask(question: Question) -> String {
+    let mut answer = AnswerKind::Single(String::new());
+    question.ask(&mut answer);
+    match answer {
+        AnswerKind::Single(s) => s,
+        _ => unreachable!(),
+    }
+}
+
+/// Finds the git repos under `root` and describes each one as a `Project` of
+/// the workspace `name`. Hidden directories, the insides of repos and the
+/// directories in `skip` (e.g. nested workspaces) are not searched.
+pub fn scan(
+    name: &WorkspaceName,
+    root: &Path,
+    skip: &[PathBuf],
+) -> Result<Vec<(PathBuf, Project)>, EnzoError> {
+    let mut repos = vec![];
+    find_repos(root, skip, SCAN_DEPTH, &mut repos)?;
+    repos.sort();
+
+    let mut projects = vec![];
+    for path in repos {
+        let project_name = match path.file_name() {
+            Some(file_name) => file_name.to_string_lossy().into_owned(),
+            None => continue,
+        };
+        let src = git::origin_url(&path).unwrap_or_default();
+        let file = path.join("enzo.yaml");
+        let todos = if file.exists() {
+            match todos::read_from(&file) {
+                Ok(todos) => todos,
+                Err(e) => {
+                    // registering it without its todos could later overwrite them
+                    utils::warning(format!(
+                        "skipping {:?}, could not read {:?}: {}",
+                        path, file, e
+                    ));
+                    continue;
+                }
+            }
+        } else {
+            vec![]
+        };
+        let project = Project::new(project_name, name.clone(), src.unwrap_or_default(), todos);
+        projects.push((path, project));
+    }
+    Ok(projects)
+}
+
+fn find_repos(
+    dir: &Path,
+    skip: &[PathBuf],
+    depth: usize,
+    repos: &mut Vec<PathBuf>,
+) -> Result<(), EnzoError> {
+    if dir.join(".git").exists() {
+        repos.push(dir.to_path_buf());
+        return Ok(());
+    }
+    if depth == 0 {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                utils::warning(format!("skipping an entry of {:?}: {}", dir, e));
+                continue;
+            }
+        };
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if hidden || !is_dir || skip.contains(&path) {
+            continue;
+        }
+        // one unreadable directory shouldn't stop the whole scan
+        if let Err(e) = find_repos(&path, skip, depth - 1, repos) {
+            utils::warning(format!("skipping {:?}: {}", path, e));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_names() {
+        let hackgt = WorkspaceName::from("hackgt");
+        let websites = WorkspaceName::from("hackgt.websites");
+        let college = WorkspaceName::from("college");
+        let hw = WorkspaceName::from("college/hw");
+
+        assert!(websites.is_nested_in(&hackgt));
+        assert!(hw.is_nested_in(&college));
+        assert!(!hackgt.is_nested_in(&websites));
+        assert!(!hackgt.is_nested_in(&hackgt));
+        assert!(!WorkspaceName::from("hackgtx").is_nested_in(&hackgt));
+    }
 
-    // TODO change in the future to scan the directory and populate projects vec
-    let data = WorkspaceData::new(base, vec![]);
+    #[test]
+    fn skip_projects_with_broken_todos() {
+        let root = std::env::temp_dir().join(format!("enzo-scan-{}", std::process::id()));
+        for repo in &["fine", "broken"] {
+            fs::create_dir_all(root.join(repo).join(".git")).unwrap();
+        }
+        fs::write(root.join("broken").join("enzo.yaml"), "todos: [").unwrap();
 
-    Ok((WorkspaceName(name.to_string()), data))
+        let projects = scan(&"ws".into(), &root, &[]).unwrap();
+        let paths = projects.iter().map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(paths, vec![&root.join("fine")]);
+        fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -3,12 +3,28 @@ use crate::workspace::WorkspaceName;
 
 use serde::{Deserialize, Serialize};
 
+/// Where the todos of a project are kept. `Private` todos only live in the
+/// global config, `Repo` todos live in the project's `enzo.yaml` so that
+/// everyone working on it sees them. Writing to the repo has to be opted into.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoStore {
+    Repo,
+    #[default]
+    Private,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Project {
     name: String,
     pub src: String,
     workspace: WorkspaceName,
     pub todos: Vec<Todo>,
+    #[serde(default)]
+    todo_store: TodoStore,
+    /// The todos in `enzo.yaml` the last time they were synced with `todos`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    seen_todos: Vec<Todo>,
 }
 
 impl Project {
@@ -17,7 +33,41 @@ impl Project {
             name,
             workspace,
             src,
+            seen_todos: todos.clone(),
             todos,
+            todo_store: TodoStore::default(),
         }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    pub fn workspace(&self) -> &WorkspaceName {
+        &self.workspace
+    }
+
+    pub fn set_workspace(&mut self, workspace: WorkspaceName) {
+        self.workspace = workspace;
+    }
+
+    pub fn todo_store(&self) -> TodoStore {
+        self.todo_store
+    }
+
+    pub fn set_todo_store(&mut self, store: TodoStore) {
+        self.todo_store = store;
+    }
+
+    pub fn seen_todos(&self) -> &[Todo] {
+        &self.seen_todos
+    }
+
+    pub fn set_seen_todos(&mut self, todos: Vec<Todo>) {
+        self.seen_todos = todos;
+    }
 }